@@ -0,0 +1,538 @@
+// The Porter stemmer as it was before the steps moved to an ASCII byte buffer
+// with a precomputed consonant mask: the String-based steps that rescan the
+// word for every measure and vowel check. It is kept here unchanged, but for
+// this comment and the lints it would otherwise fail, so that the bench can
+// time it next to the crate.
+
+#![allow(clippy::all)]
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt;
+use std::iter::Skip;
+use std::str::Chars;
+
+type Result<T, StemError> = std::result::Result<T, StemError>;
+#[derive(Debug, Clone)]
+pub struct StemError;
+
+impl fmt::Display for StemError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid Input")
+    }
+}
+// A \consonant\ in a word is a letter other than A, E, I, O or U, and other
+// than Y preceded by a consonant. (The fact that the term `consonant' is
+// defined to some extent in terms of itself does not make it ambiguous.) So in
+// TOY the consonants are T and Y, and in SYZYGY they are S, Z and G. If a
+// letter is not a consonant it is a \vowel\.
+
+fn is_consonant(current: Option<char>, previous: Option<char>) -> bool {
+    match current {
+        Some('a') | Some('e') | Some('i') | Some('o') | Some('u') => false,
+        Some('y') => match previous {
+            Some('a') | Some('e') | Some('i') | Some('o') | Some('u') => true,
+            _ => false,
+        },
+        _ => true,
+    }
+}
+
+fn has_vowel(word: &str) -> bool {
+    has_vowel_with_limit(&word, word.len())
+}
+
+fn has_vowel_with_limit(word: &str, max: usize) -> bool {
+    let my_chars = word.chars();
+    let mut previous: Option<char>;
+    let mut current: Option<char> = None;
+    let mut count: usize = 1;
+    for my_char in my_chars {
+        if count > max {
+            return false;
+        }
+        previous = current;
+        current = Some(my_char);
+        if !is_consonant(current, previous) {
+            return true;
+        };
+        count += 1;
+    }
+    false
+}
+
+fn measure(word: &str) -> usize {
+    measure_with_limit(&word, word.len())
+}
+
+fn measure_with_limit(word: &str, max: usize) -> usize {
+    let my_chars = word.chars();
+    let mut previous: Option<char>;
+    let mut current: Option<char> = None;
+    let mut count = 0;
+    let mut current_consonant = false;
+    let mut begin_counting = false;
+    for (position, my_char) in my_chars.enumerate() {
+        if position == max {
+            return count;
+        }
+        previous = current;
+        current = Some(my_char);
+        if !begin_counting {
+            if !is_consonant(current, previous) {
+                begin_counting = true;
+            }
+        } else if current_consonant != is_consonant(current, previous) {
+            current_consonant = !current_consonant;
+            // Only increase count when we go from vowel to consonant
+            if current_consonant {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn get_char_at_position(word: &str, position: usize) -> Option<char> {
+    if position == 0 {
+        return None;
+    }
+    word.chars().nth(position - 1)
+}
+
+thread_local! {
+    static STEMMER: RefCell<Stemmer> = RefCell::new(Stemmer::new());
+}
+
+fn validate(word: &str) -> Result<(), StemError> {
+    for my_char in word.chars() {
+        if !my_char.is_ascii() {
+            return Err(StemError);
+        }
+    }
+    Ok(())
+}
+
+pub fn stem(word: String) -> Result<String, StemError> {
+    stem_str(&word).map(Cow::into_owned)
+}
+
+/// Stems `word` without taking ownership of it.
+///
+/// The input is returned borrowed when the stem is a prefix of it (no rule
+/// fired, or the rules only removed a suffix), so an owned `String` is only
+/// allocated when a suffix is actually rewritten or the word needed
+/// lowercasing.
+pub fn stem_str(word: &str) -> Result<Cow<'_, str>, StemError> {
+    validate(word)?;
+    if word.len() <= 2 {
+        return Ok(Cow::Borrowed(word));
+    }
+    let trimmed = word.trim();
+    STEMMER.with(|stemmer| {
+        let mut stemmer = stemmer.borrow_mut();
+        let my_word = stemmer.stem_buffer(trimmed);
+        if trimmed.starts_with(my_word) {
+            Ok(Cow::Borrowed(&trimmed[..my_word.len()]))
+        } else {
+            Ok(Cow::Owned(my_word.to_string()))
+        }
+    })
+}
+
+/// A reusable Porter stemmer.
+///
+/// The stemmer owns the scratch buffer the steps run in, so once it has seen
+/// a word at least as long as the current one, `stem_into` performs no heap
+/// allocation.
+#[derive(Debug, Default, Clone)]
+pub struct Stemmer {
+    buffer: String,
+}
+
+impl Stemmer {
+    pub fn new() -> Stemmer {
+        Stemmer::default()
+    }
+
+    /// Stems `word` into `out`, replacing its contents. The output is the same
+    /// as that of `stem`.
+    pub fn stem_into(&mut self, word: &str, out: &mut String) -> Result<(), StemError> {
+        validate(word)?;
+        out.clear();
+        if word.len() <= 2 {
+            out.push_str(word);
+        } else {
+            out.push_str(self.stem_buffer(word.trim()));
+        }
+        Ok(())
+    }
+
+    // None of the steps grow the word beyond its original length, so the
+    // buffer never reallocates once it can hold `word`.
+    fn stem_buffer(&mut self, word: &str) -> &str {
+        let mut my_word = std::mem::take(&mut self.buffer);
+        my_word.clear();
+        my_word.push_str(word);
+        my_word.make_ascii_lowercase();
+        my_word = stem1a(my_word);
+        my_word = stem1b(my_word);
+        my_word = stem1c(my_word);
+        my_word = stem2(my_word);
+        my_word = stem3(my_word);
+        my_word = stem4(my_word);
+        my_word = stem5a(my_word);
+        my_word = stem5b(my_word);
+        self.buffer = my_word;
+        &self.buffer
+    }
+}
+
+fn stem1a(mut word: String) -> String {
+    if word.ends_with("sses") || word.ends_with("ies") {
+        word.truncate(word.len() - 2);
+    } else if word.ends_with("ss") {
+    } else if word.ends_with('s') {
+        word.truncate(word.len() - 1);
+    }
+    word
+}
+
+fn stem1b(mut word: String) -> String {
+    if word.ends_with("eed") {
+        if measure_with_limit(&word, word.len() - 3) > 0 {
+            word.truncate(word.len() - 1);
+        }
+    } else if word.ends_with("ed") {
+        if has_vowel_with_limit(&word, word.len() - 2) {
+            word.truncate(word.len() - 2);
+            return stem1bresolve(word);
+        }
+    } else if word.ends_with("ing") && has_vowel_with_limit(&word, word.len() - 3) {
+        word.truncate(word.len() - 3);
+        return stem1bresolve(word);
+    }
+    word
+}
+
+fn stem1bresolve(mut word: String) -> String {
+    let mut my_chars: Skip<Chars>;
+    let mut one: Option<char> = None;
+    let mut two: Option<char> = None;
+    if word.len() >= 4 {
+        my_chars = word.chars().skip(word.len() - 4);
+        one = my_chars.next();
+        two = my_chars.next();
+    } else if word.len() == 3 {
+        my_chars = word.chars().skip(word.len() - 3);
+        two = my_chars.next();
+    } else if word.len() == 2 {
+        my_chars = word.chars().skip(word.len() - 2);
+    } else {
+        return word;
+    }
+    let three = my_chars.next();
+    let four = my_chars.next();
+    if word.ends_with("at") || word.ends_with("bl") || word.ends_with("iz") {
+        word.push('e');
+    } else if four == three && is_consonant(three, two) {
+        match three {
+            Some('l') | Some('s') | Some('z') => return word,
+            _ => {
+                word.pop();
+            }
+        }
+    } else if measure(&word) == 1
+        && is_consonant(four, three)
+        && !is_consonant(three, two)
+        && is_consonant(two, one)
+        && !word.ends_with('w')
+        && !word.ends_with('x')
+        && !word.ends_with('y')
+    {
+        word.push('e');
+    }
+    word
+}
+
+fn stem1c(mut word: String) -> String {
+    let l = word.pop();
+    if has_vowel(&word) && l == Some('y') {
+        word.push('i');
+    } else {
+        word.push(l.unwrap());
+    }
+    word
+}
+
+fn stem2(mut word: String) -> String {
+    let letter = get_char_at_position(&word, word.len() - 1);
+    match letter {
+        Some('a') => {
+            if word.ends_with("ational") && measure_with_limit(&word, word.len() - 7) > 0 {
+                word.truncate(word.len() - 7);
+                word.push_str("ate");
+            } else if word.ends_with("tional") && measure_with_limit(&word, word.len() - 6) > 0 {
+                word.truncate(word.len() - 6);
+                word.push_str("tion");
+            }
+        }
+        Some('c') => {
+            if word.ends_with("enci") && measure_with_limit(&word, word.len() - 4) > 0 {
+                word.truncate(word.len() - 4);
+                word.push_str("ence");
+            } else if word.ends_with("anci") && measure_with_limit(&word, word.len() - 4) > 0 {
+                word.truncate(word.len() - 4);
+                word.push_str("ance");
+            }
+        }
+        Some('e') => {
+            if word.ends_with("izer") && measure_with_limit(&word, word.len() - 4) > 0 {
+                word.truncate(word.len() - 4);
+                word.push_str("ize");
+            }
+        }
+        Some('l') => {
+            if word.ends_with("abli") && measure_with_limit(&word, word.len() - 4) > 0 {
+                word.truncate(word.len() - 4);
+                word.push_str("able");
+            } else if word.ends_with("alli") && measure_with_limit(&word, word.len() - 4) > 0 {
+                word.truncate(word.len() - 4);
+                word.push_str("al");
+            } else if word.ends_with("entli") && measure_with_limit(&word, word.len() - 5) > 0 {
+                word.truncate(word.len() - 5);
+                word.push_str("ent");
+            } else if word.ends_with("ousli") && measure_with_limit(&word, word.len() - 5) > 0 {
+                word.truncate(word.len() - 5);
+                word.push_str("ous");
+            } else if word.ends_with("eli") && measure_with_limit(&word, word.len() - 3) > 0 {
+                word.truncate(word.len() - 3);
+                word.push_str("e");
+            }
+        }
+        Some('o') => {
+            if word.ends_with("ization") && measure_with_limit(&word, word.len() - 7) > 0 {
+                word.truncate(word.len() - 7);
+                word.push_str("ize");
+            } else if word.ends_with("ation") && measure_with_limit(&word, word.len() - 5) > 0 {
+                word.truncate(word.len() - 5);
+                word.push_str("ate");
+            } else if word.ends_with("ator") && measure_with_limit(&word, word.len() - 4) > 0 {
+                word.truncate(word.len() - 4);
+                word.push_str("ate");
+            }
+        }
+        Some('s') => {
+            if word.ends_with("alism") && measure_with_limit(&word, word.len() - 5) > 0 {
+                word.truncate(word.len() - 5);
+                word.push_str("al");
+            } else if word.ends_with("iveness") && measure_with_limit(&word, word.len() - 7) > 0 {
+                word.truncate(word.len() - 7);
+                word.push_str("ive");
+            } else if word.ends_with("fulness") && measure_with_limit(&word, word.len() - 7) > 0 {
+                word.truncate(word.len() - 7);
+                word.push_str("ful");
+            } else if word.ends_with("ousness") && measure_with_limit(&word, word.len() - 7) > 0 {
+                word.truncate(word.len() - 7);
+                word.push_str("ous");
+            }
+        }
+        Some('t') => {
+            if word.ends_with("aliti") && measure_with_limit(&word, word.len() - 5) > 0 {
+                word.truncate(word.len() - 5);
+                word.push_str("al");
+            } else if word.ends_with("iviti") && measure_with_limit(&word, word.len() - 5) > 0 {
+                word.truncate(word.len() - 5);
+                word.push_str("ive");
+            } else if word.ends_with("biliti") && measure_with_limit(&word, word.len() - 6) > 0 {
+                word.truncate(word.len() - 6);
+                word.push_str("ble");
+            }
+        }
+        _ => {}
+    }
+    word
+}
+fn stem3(mut word: String) -> String {
+    let letter = get_char_at_position(&word, word.len() - 1);
+    match letter {
+        Some('a') => {
+            if word.ends_with("ical") && measure_with_limit(&word, word.len() - 4) > 0 {
+                word.truncate(word.len() - 4);
+                word.push_str("ic");
+            }
+        }
+        Some('t') => {
+            if (word.ends_with("icate") && measure_with_limit(&word, word.len() - 5) > 0)
+                || (word.ends_with("iciti") && measure_with_limit(&word, word.len() - 5) > 0)
+            {
+                word.truncate(word.len() - 5);
+                word.push_str("ic");
+            }
+        }
+        Some('u') => {
+            if word.ends_with("ful") && measure_with_limit(&word, word.len() - 3) > 0 {
+                word.truncate(word.len() - 3);
+            }
+        }
+        Some('s') => {
+            if word.ends_with("ness") && measure_with_limit(&word, word.len() - 4) > 0 {
+                word.truncate(word.len() - 4);
+            }
+        }
+        Some('v') => {
+            if word.ends_with("ative") && measure_with_limit(&word, word.len() - 5) > 0 {
+                word.truncate(word.len() - 5);
+            }
+        }
+        Some('z') => {
+            if word.ends_with("alize") && measure_with_limit(&word, word.len() - 5) > 0 {
+                word.truncate(word.len() - 5);
+                word.push_str("al");
+            }
+        }
+        _ => {}
+    }
+    word
+}
+
+fn stem4(mut word: String) -> String {
+    let letter = get_char_at_position(&word, word.len() - 1);
+    match letter {
+        Some('a') => {
+            if word.ends_with("al") && measure_with_limit(&word, word.len() - 2) > 1 {
+                word.truncate(word.len() - 2);
+            }
+        }
+        Some('c') => {
+            if (word.ends_with("ance") && measure_with_limit(&word, word.len() - 4) > 1)
+                || (word.ends_with("ence") && measure_with_limit(&word, word.len() - 4) > 1)
+            {
+                word.truncate(word.len() - 4);
+            }
+        }
+        Some('e') => {
+            if word.ends_with("er") && measure_with_limit(&word, word.len() - 2) > 1 {
+                word.truncate(word.len() - 2);
+            }
+        }
+        Some('i') => {
+            if word.ends_with("ic") && measure_with_limit(&word, word.len() - 2) > 1 {
+                word.truncate(word.len() - 2);
+            }
+        }
+        Some('l') => {
+            if (word.ends_with("able") && measure_with_limit(&word, word.len() - 4) > 1)
+                || (word.ends_with("ible") && measure_with_limit(&word, word.len() - 4) > 1)
+            {
+                word.truncate(word.len() - 4);
+            }
+        }
+        Some('n') => {
+            if word.ends_with("ant") && measure_with_limit(&word, word.len() - 3) > 1 {
+                word.truncate(word.len() - 3);
+            } else if word.ends_with("ement") && measure_with_limit(&word, word.len() - 5) > 1 {
+                word.truncate(word.len() - 5);
+            } else if word.ends_with("ment") && measure_with_limit(&word, word.len() - 4) > 1 {
+                word.truncate(word.len() - 4);
+            } else if word.ends_with("ent") && measure_with_limit(&word, word.len() - 3) > 1 {
+                word.truncate(word.len() - 3);
+            }
+        }
+        Some('o') => {
+            if word.ends_with("ion") && measure_with_limit(&word, word.len() - 4) > 1 {
+                let letter2 = get_char_at_position(&word, word.len() - 3);
+                match letter2 {
+                    Some('s') | Some('t') => word.truncate(word.len() - 3),
+                    _ => {}
+                }
+            }
+        }
+        Some('s') => {
+            if word.ends_with("ism") && measure_with_limit(&word, word.len() - 3) > 1 {
+                word.truncate(word.len() - 3);
+            }
+        }
+        Some('t') => {
+            if (word.ends_with("ate") && measure_with_limit(&word, word.len() - 3) > 1)
+                || (word.ends_with("iti") && measure_with_limit(&word, word.len() - 3) > 1)
+            {
+                word.truncate(word.len() - 3);
+            }
+        }
+        Some('u') => {
+            if word.ends_with("ous") && measure_with_limit(&word, word.len() - 3) > 1 {
+                word.truncate(word.len() - 3);
+            }
+        }
+        Some('v') => {
+            if word.ends_with("ive") && measure_with_limit(&word, word.len() - 3) > 1 {
+                word.truncate(word.len() - 3);
+            }
+        }
+        Some('z') => {
+            if word.ends_with("ize") && measure_with_limit(&word, word.len() - 3) > 1 {
+                word.truncate(word.len() - 3);
+            }
+        }
+        _ => {}
+    }
+    word
+}
+
+fn stem5a(mut word: String) -> String {
+    let y = word.pop().unwrap();
+    let mut my_chars: Skip<Chars>;
+    let mut one: Option<char> = None;
+    let mut two: Option<char> = None;
+    if word.len() >= 4 {
+        my_chars = word.chars().skip(word.len() - 4);
+        one = my_chars.next();
+        two = my_chars.next();
+    } else if word.len() == 3 {
+        my_chars = word.chars().skip(word.len() - 3);
+        two = my_chars.next();
+    } else if word.len() == 2 {
+        my_chars = word.chars().skip(word.len() - 2);
+    } else {
+        word.push(y);
+        return word;
+    }
+    let three = my_chars.next();
+    let four = my_chars.next();
+    if y == 'e' {
+        match measure(&word) {
+            0 => {}
+            1 => {
+                if !is_consonant(two, one) || is_consonant(three, two) || !is_consonant(four, three)
+                {
+                    return word;
+                } else {
+                    match four {
+                        Some('x') | Some('y') | Some('w') => return word,
+                        _ => word.push(y),
+                    }
+                }
+            }
+            _ => return word,
+        }
+    } else {
+        word.push(y);
+    }
+    word
+}
+
+fn stem5b(mut word: String) -> String {
+    if word.ends_with('l') {
+        let mut my_chars = word.chars().skip(word.len() - 3);
+
+        let one = my_chars.next();
+        let two = my_chars.next();
+        let three = my_chars.next();
+        let y = word.pop().unwrap();
+        if !(measure(&word) > 1 && is_consonant(two, one) && is_consonant(three, two)) {
+            word.push(y);
+        }
+    }
+    word
+}
@@ -0,0 +1,107 @@
+// Throughput of the stemming entry points over a generated vocabulary.
+//
+// Run with `cargo bench`. The vocabulary mixes short inflected words with
+// long derived ones, where rescanning the word for every rule check used to
+// dominate. Each entry point is timed for the crate and for `baseline`, the
+// String-based stemmer it had before the consonant mask.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use stemmer::{stem, stem_str, Stemmer};
+
+mod baseline;
+
+const STEMS: &[&str] = &[
+    "connect",
+    "general",
+    "oscill",
+    "relat",
+    "condition",
+    "sensit",
+    "hope",
+    "adjust",
+    "electr",
+    "form",
+    "depend",
+    "communic",
+    "nation",
+    "institution",
+    "compart",
+    "ration",
+];
+
+const SUFFIXES: &[&str] = &[
+    "",
+    "s",
+    "ed",
+    "ing",
+    "ation",
+    "ational",
+    "izations",
+    "ousness",
+    "iveness",
+    "fulness",
+    "ements",
+    "abilities",
+    "alizing",
+    "ativeness",
+    "icalities",
+];
+
+fn vocabulary() -> Vec<String> {
+    let mut words = Vec::new();
+    for stem in STEMS {
+        for suffix in SUFFIXES {
+            words.push(format!("{}{}", stem, suffix));
+            words.push(format!("anti{}{}", stem, suffix));
+            words.push(format!("counterrevolutionary{}{}", stem, suffix));
+        }
+    }
+    words
+}
+
+fn run<F: FnMut(&str)>(name: &str, words: &[String], mut f: F) {
+    let mut stemmed = 0u64;
+    let mut elapsed = Duration::default();
+    while elapsed < Duration::from_secs(1) {
+        let start = Instant::now();
+        for word in words {
+            f(black_box(word));
+        }
+        elapsed += start.elapsed();
+        stemmed += words.len() as u64;
+    }
+    println!(
+        "{:<20} {:>8.1} ns/word {:>12.0} words/s",
+        name,
+        elapsed.as_nanos() as f64 / stemmed as f64,
+        stemmed as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let words = vocabulary();
+    run("stem", &words, |word| {
+        black_box(stem(word.to_string()).unwrap());
+    });
+    run("baseline::stem", &words, |word| {
+        black_box(baseline::stem(word.to_string()).unwrap());
+    });
+    run("stem_str", &words, |word| {
+        black_box(stem_str(word).unwrap());
+    });
+    run("baseline::stem_str", &words, |word| {
+        black_box(baseline::stem_str(word).unwrap());
+    });
+    let mut stemmer = Stemmer::new();
+    let mut out = String::new();
+    run("Stemmer::stem_into", &words, |word| {
+        stemmer.stem_into(word, &mut out).unwrap();
+        black_box(&out);
+    });
+    let mut stemmer = baseline::Stemmer::new();
+    run("baseline::stem_into", &words, |word| {
+        stemmer.stem_into(word, &mut out).unwrap();
+        black_box(&out);
+    });
+}
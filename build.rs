@@ -0,0 +1,56 @@
+// Compiles the Snowball sources in `snowball/` to Rust, one module per
+// language, for `stemmer::snowball` to include. The compiler is the crate's
+// own, built into this script from its source.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[path = "src/snowball/compiler/mod.rs"]
+#[allow(dead_code)]
+mod compiler;
+
+fn main() {
+    println!("cargo:rerun-if-changed=snowball");
+    println!("cargo:rerun-if-changed=src/snowball/compiler");
+    let mut languages: Vec<String> = fs::read_dir("snowball")
+        .expect("cannot read snowball/")
+        .filter_map(|entry| {
+            let path = entry.expect("cannot read snowball/").path();
+            match path.extension() {
+                Some(extension) if extension == "sbl" => {
+                    Some(path.file_stem()?.to_str()?.to_string())
+                }
+                _ => None,
+            }
+        })
+        .collect();
+    languages.sort();
+
+    let mut out = String::from("// Generated by build.rs from the sources in snowball/.\n\n");
+    out.push_str("/// The languages of the Snowball stemmers that come with the crate.\n");
+    out.push_str(&format!(
+        "pub const LANGUAGES: &[&str] = &{:?};\n\n",
+        languages
+    ));
+    out.push_str("// The `stem` external of the stemmer for `language`.\n");
+    out.push_str("pub(super) fn external(language: &str) -> Option<(&'static str, fn(&mut stemmer::snowball::runtime::Env) -> bool)> {\n");
+    out.push_str("    match language {\n");
+    for language in &languages {
+        out.push_str(&format!(
+            "        {:?} => Some(({:?}, {}::stem)),\n",
+            language, language, language
+        ));
+    }
+    out.push_str("        _ => None,\n    }\n}\n");
+    for language in &languages {
+        let path = Path::new("snowball").join(language).with_extension("sbl");
+        let source =
+            fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let rust =
+            compiler::compile(&source).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        out.push_str(&format!("\nmod {} {{\n{}}}\n", language, rust));
+    }
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is not set");
+    fs::write(Path::new(&out_dir).join("snowball.rs"), out).expect("cannot write snowball.rs");
+}
@@ -0,0 +1,369 @@
+// Stems the words in files or standard input, a chunk at a time, so inputs of
+// any size can be streamed through it.
+
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process;
+
+use stemmer::conflation;
+use stemmer::index::StemIndex;
+use stemmer::tokenize;
+
+// How many bytes of input are read at a time.
+const CHUNK: usize = 64 * 1024;
+
+const USAGE: &str = "\
+Usage: stem [OPTIONS] [FILE]...
+
+Stems every word in the FILEs, or in standard input when no FILE is given or
+FILE is -. Punctuation around a word is not part of it, and contractions are
+split in two: \"don't\" gives \"do\" and \"n't\".
+
+Options:
+  -f, --format FORMAT  how to write the stems:
+                         lines  one stem per line (default)
+                         pairs  each word and its stem, separated by a tab
+                         text   the input with each word replaced by its stem,
+                                keeping everything around it
+  -e, --errors ACTION  what to do with a word that cannot be stemmed:
+                         fail   report it and stop (default)
+                         skip   leave it out
+                         pass   write it unchanged
+  -c, --classes FORMAT instead of stemming word by word, split the input
+                       into tokens, group the distinct tokens by stem and
+                       write the groups, largest first, as json or tsv;
+                       tokens that cannot be stemmed are left out
+  -h, --help           print this message";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Lines,
+    Pairs,
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnError {
+    Fail,
+    Skip,
+    Pass,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Classes {
+    Json,
+    Tsv,
+}
+
+#[derive(Debug)]
+struct Options {
+    format: Format,
+    on_error: OnError,
+    classes: Option<Classes>,
+    files: Vec<String>,
+}
+
+enum Failure {
+    Io(io::Error),
+    Open(String, io::Error),
+    Stem {
+        input: String,
+        line: usize,
+        word: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Io(error) => write!(f, "{}", error),
+            Failure::Open(file, error) => write!(f, "{}: {}", file, error),
+            Failure::Stem {
+                input,
+                line,
+                word,
+                message,
+            } => write!(f, "{}:{}: {:?}: {}", input, line, word, message),
+        }
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Failure {
+        Failure::Io(error)
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        format: Format::Lines,
+        on_error: OnError::Fail,
+        classes: None,
+        files: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => (&arg[..i], Some(arg[i + 1..].to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} needs a value", name))
+        };
+        match name {
+            "-h" | "--help" => return Ok(None),
+            "-f" | "--format" => {
+                options.format = match value(name)?.as_str() {
+                    "lines" => Format::Lines,
+                    "pairs" => Format::Pairs,
+                    "text" => Format::Text,
+                    other => return Err(format!("unknown format {:?}", other)),
+                }
+            }
+            "-e" | "--errors" => {
+                options.on_error = match value(name)?.as_str() {
+                    "fail" => OnError::Fail,
+                    "skip" => OnError::Skip,
+                    "pass" => OnError::Pass,
+                    other => return Err(format!("unknown error action {:?}", other)),
+                }
+            }
+            "-c" | "--classes" => {
+                options.classes = match value(name)?.as_str() {
+                    "json" => Some(Classes::Json),
+                    "tsv" => Some(Classes::Tsv),
+                    other => return Err(format!("unknown class format {:?}", other)),
+                }
+            }
+            "--" => {
+                options.files.extend(args);
+                break;
+            }
+            _ if arg.len() > 1 && arg.starts_with('-') => {
+                return Err(format!("unknown option {}", arg))
+            }
+            _ => options.files.push(arg),
+        }
+    }
+    if options.files.is_empty() {
+        options.files.push("-".to_string());
+    }
+    Ok(Some(options))
+}
+
+fn run<W: Write>(options: &Options, out: &mut W) -> Result<(), Failure> {
+    if let Some(format) = options.classes {
+        return write_classes(options, format, out);
+    }
+    for file in &options.files {
+        if file == "-" {
+            let stdin = io::stdin();
+            stem_input(stdin.lock(), "<stdin>", options, out)?;
+        } else {
+            let input = File::open(file).map_err(|e| Failure::Open(file.clone(), e))?;
+            stem_input(input, file, options, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_classes<W: Write>(options: &Options, format: Classes, out: &mut W) -> Result<(), Failure> {
+    let mut index = StemIndex::new();
+    for file in &options.files {
+        if file == "-" {
+            let stdin = io::stdin();
+            conflation::add_corpus(&mut index, stdin.lock())?;
+        } else {
+            let input = File::open(file).map_err(|e| Failure::Open(file.clone(), e))?;
+            conflation::add_corpus(&mut index, BufReader::new(input))?;
+        }
+    }
+    let classes = conflation::classes(&index);
+    match format {
+        Classes::Json => conflation::write_json(&classes, out)?,
+        Classes::Tsv => conflation::write_tsv(&classes, out)?,
+    }
+    Ok(())
+}
+
+fn stem_input<R: Read, W: Write>(
+    mut input: R,
+    name: &str,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), Failure> {
+    let mut buffer = Vec::with_capacity(2 * CHUNK);
+    let mut line = 1;
+    loop {
+        let start = buffer.len();
+        buffer.resize(start + CHUNK, 0);
+        let read = loop {
+            match input.read(&mut buffer[start..]) {
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                result => break result?,
+            }
+        };
+        buffer.truncate(start + read);
+        if read == 0 {
+            return stem_chunk(&buffer, name, &mut line, options, out);
+        }
+        // The last token may go on in the next chunk, so it is kept back
+        // unless it is already far too long to stem.
+        let end = match buffer.iter().rposition(|&b| is_break(b)) {
+            _ if buffer.len() >= CHUNK * 2 => char_boundary(&buffer),
+            Some(i) => i + 1,
+            None => 0,
+        };
+        stem_chunk(&buffer[..end], name, &mut line, options, out)?;
+        buffer.drain(..end);
+    }
+}
+
+// The length of `bytes` without a character cut off at its end, so that a token
+// split for being too long is not split inside a character as well.
+fn char_boundary(bytes: &[u8]) -> usize {
+    // A character is at most four bytes, and only its first is not 10xxxxxx.
+    match bytes.iter().rev().take(4).position(|&b| b & 0xC0 != 0x80) {
+        Some(i) => {
+            let start = bytes.len() - 1 - i;
+            let width = match bytes[start].leading_ones() {
+                0 => 1,
+                n => n as usize,
+            };
+            if start + width > bytes.len() {
+                start
+            } else {
+                bytes.len()
+            }
+        }
+        None => bytes.len(),
+    }
+}
+
+// Whether the byte always ends a token. Letters, digits, apostrophes, '.' and
+// ',' can all be inside one, as can any byte of a non-ASCII character.
+fn is_break(b: u8) -> bool {
+    b.is_ascii() && !b.is_ascii_alphanumeric() && !matches!(b, b'\'' | b'.' | b',')
+}
+
+// Stems the tokens of a chunk of input that ends between tokens. `line` is the
+// line the chunk starts on, and is moved on to the line it ends on.
+fn stem_chunk<W: Write>(
+    mut chunk: &[u8],
+    name: &str,
+    line: &mut usize,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), Failure> {
+    while !chunk.is_empty() {
+        let (text, invalid) = match std::str::from_utf8(chunk) {
+            Ok(text) => (text, &chunk[chunk.len()..]),
+            Err(error) => {
+                let (valid, rest) = chunk.split_at(error.valid_up_to());
+                let len = error.error_len().unwrap_or(rest.len());
+                (std::str::from_utf8(valid).unwrap(), &rest[..len])
+            }
+        };
+        let mut gap = 0;
+        for (token, stem) in tokenize::stem_text(text) {
+            write_gap(&text.as_bytes()[gap..token.span.start], line, options, out)?;
+            gap = token.span.end;
+            let stem = stem.as_deref().map_err(|e| e.to_string());
+            write_word(token.text.as_bytes(), stem, name, *line, options, out)?;
+        }
+        write_gap(&text.as_bytes()[gap..], line, options, out)?;
+        if !invalid.is_empty() {
+            let message = "Invalid Input: not valid UTF-8".to_string();
+            write_word(invalid, Err(message), name, *line, options, out)?;
+        }
+        chunk = &chunk[text.len() + invalid.len()..];
+    }
+    Ok(())
+}
+
+// Writes the text between two tokens, which only the text format keeps.
+fn write_gap<W: Write>(
+    gap: &[u8],
+    line: &mut usize,
+    options: &Options,
+    out: &mut W,
+) -> io::Result<()> {
+    *line += gap.iter().filter(|&&b| b == b'\n').count();
+    if options.format == Format::Text {
+        out.write_all(gap)?;
+    }
+    Ok(())
+}
+
+fn write_word<W: Write>(
+    word: &[u8],
+    stem: Result<&str, String>,
+    name: &str,
+    line: usize,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), Failure> {
+    let output = match (stem, options.on_error) {
+        (Ok(stem), _) => stem.as_bytes(),
+        (Err(_), OnError::Skip) => return Ok(()),
+        (Err(_), OnError::Pass) => word,
+        (Err(message), OnError::Fail) => {
+            return Err(Failure::Stem {
+                input: name.to_string(),
+                line,
+                word: String::from_utf8_lossy(word).into_owned(),
+                message,
+            })
+        }
+    };
+    match options.format {
+        Format::Lines => {
+            out.write_all(output)?;
+            out.write_all(b"\n")?;
+        }
+        Format::Pairs => {
+            out.write_all(word)?;
+            out.write_all(b"\t")?;
+            out.write_all(output)?;
+            out.write_all(b"\n")?;
+        }
+        Format::Text => out.write_all(output)?,
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("stem: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let result = run(&options, &mut out);
+    let flushed = out.flush().map_err(Failure::Io);
+    match result.and(flushed) {
+        Ok(()) => {}
+        // The reader went away, as with `stem big.txt | head`.
+        Err(Failure::Io(ref error)) if error.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(failure @ Failure::Stem { .. }) => {
+            eprintln!("stem: {}", failure);
+            process::exit(1);
+        }
+        Err(failure) => {
+            eprintln!("stem: {}", failure);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,336 @@
+// Memoizing wrappers around a stemmer. Most tokens of running text are
+// repeats, so a small cache of recent words saves running the steps again.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::sync::{Mutex, PoisonError};
+
+use crate::{Porter, Stem, StemError};
+
+/// How often a cache had the stem of a word.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// The fraction of lookups that were hits, or 0 before any lookup.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+struct Slot {
+    word: String,
+    stem: String,
+    referenced: bool,
+}
+
+// A CLOCK cache of stems with its statistics.
+#[derive(Default)]
+struct Cache {
+    slots: Vec<Slot>,
+    index: HashMap<String, usize>,
+    hand: usize,
+    capacity: usize,
+    stats: CacheStats,
+}
+
+impl Cache {
+    fn new(capacity: usize) -> Cache {
+        Cache {
+            capacity,
+            ..Cache::default()
+        }
+    }
+
+    // The cached stem of `word`, borrowed from it when the two are the same.
+    fn get<'a>(&mut self, word: &'a str) -> Option<Cow<'a, str>> {
+        match self.index.get(word) {
+            Some(&i) => {
+                self.stats.hits += 1;
+                let slot = &mut self.slots[i];
+                slot.referenced = true;
+                if slot.stem == word {
+                    Some(Cow::Borrowed(word))
+                } else {
+                    Some(Cow::Owned(slot.stem.clone()))
+                }
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, word: &str, stem: &str) {
+        if self.capacity == 0 || self.index.contains_key(word) {
+            return;
+        }
+        let slot = Slot {
+            word: word.to_string(),
+            stem: stem.to_string(),
+            referenced: false,
+        };
+        if self.slots.len() < self.capacity {
+            self.index.insert(slot.word.clone(), self.slots.len());
+            self.slots.push(slot);
+            return;
+        }
+        while self.slots[self.hand].referenced {
+            self.slots[self.hand].referenced = false;
+            self.hand = (self.hand + 1) % self.slots.len();
+        }
+        self.index.remove(&self.slots[self.hand].word);
+        self.index.insert(slot.word.clone(), self.hand);
+        self.slots[self.hand] = slot;
+        self.hand = (self.hand + 1) % self.slots.len();
+    }
+
+    fn clear(&mut self) {
+        *self = Cache::new(self.capacity);
+    }
+}
+
+/// A stemmer that remembers the stems of up to `capacity` recent words.
+///
+/// When the cache is full it evicts with the CLOCK algorithm, an approximation
+/// of LRU: every entry has a reference bit, set when the entry is hit. To make
+/// room, a hand sweeps over the entries, clearing the bits it finds set, and
+/// evicts the first entry whose bit is already clear. A hit costs a lookup and
+/// a flag write, with no list to reorder.
+///
+/// Words are cached as given, before trimming and lowercasing. Errors are not
+/// cached, so a word that cannot be stemmed is passed to the stemmer each time.
+///
+/// The cache sits behind a `RefCell`, so a `CachedStemmer` can be used through
+/// a shared reference but not shared between threads. See
+/// `ShardedCachedStemmer` for that.
+pub struct CachedStemmer<S = Porter> {
+    stemmer: S,
+    cache: RefCell<Cache>,
+}
+
+impl<S: Stem> CachedStemmer<S> {
+    /// Wraps `stemmer` with a cache of `capacity` words. A capacity of 0
+    /// caches nothing.
+    pub fn new(stemmer: S, capacity: usize) -> CachedStemmer<S> {
+        CachedStemmer {
+            stemmer,
+            cache: RefCell::new(Cache::new(capacity)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cache.borrow().capacity
+    }
+
+    /// The number of words in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.borrow().slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.cache.borrow().stats
+    }
+
+    /// Empties the cache and resets its statistics.
+    pub fn clear(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    /// The stemmer being wrapped.
+    pub fn get_ref(&self) -> &S {
+        &self.stemmer
+    }
+}
+
+impl<S: Stem> Stem for CachedStemmer<S> {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        if let Some(stem) = self.cache.borrow_mut().get(word) {
+            return Ok(stem);
+        }
+        let stem = self.stemmer.stem(word)?;
+        self.cache.borrow_mut().insert(word, &stem);
+        Ok(stem)
+    }
+}
+
+/// A cached stemmer that can be shared between threads.
+///
+/// The words are spread over several caches by hash, each behind its own
+/// mutex, so that threads stemming different words seldom wait for each
+/// other. The lock is not held while a word is stemmed.
+///
+/// Each shard is a CLOCK cache like that of `CachedStemmer`, and as there,
+/// words are cached as given and errors are not cached.
+pub struct ShardedCachedStemmer<S = Porter> {
+    stemmer: S,
+    shards: Vec<Mutex<Cache>>,
+    hasher: RandomState,
+}
+
+impl<S: Stem> ShardedCachedStemmer<S> {
+    /// Wraps `stemmer` with `shards` caches that hold `capacity` words between
+    /// them. There is always at least one shard.
+    pub fn new(stemmer: S, capacity: usize, shards: usize) -> ShardedCachedStemmer<S> {
+        let shards = shards.max(1);
+        ShardedCachedStemmer {
+            stemmer,
+            shards: (0..shards)
+                .map(|i| {
+                    // The first shards take the remainder.
+                    let share = capacity / shards + usize::from(i < capacity % shards);
+                    Mutex::new(Cache::new(share))
+                })
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.fold(0, |total, cache| total + cache.capacity)
+    }
+
+    /// The number of words in the caches.
+    pub fn len(&self) -> usize {
+        self.fold(0, |total, cache| total + cache.slots.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The statistics of all the shards together.
+    pub fn stats(&self) -> CacheStats {
+        self.fold(CacheStats::default(), |total, cache| CacheStats {
+            hits: total.hits + cache.stats.hits,
+            misses: total.misses + cache.stats.misses,
+        })
+    }
+
+    /// Empties the caches and resets their statistics.
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            lock(shard).clear();
+        }
+    }
+
+    /// The stemmer being wrapped.
+    pub fn get_ref(&self) -> &S {
+        &self.stemmer
+    }
+
+    fn shard(&self, word: &str) -> &Mutex<Cache> {
+        &self.shards[self.hasher.hash_one(word) as usize % self.shards.len()]
+    }
+
+    fn fold<T, F: Fn(T, &Cache) -> T>(&self, init: T, f: F) -> T {
+        self.shards
+            .iter()
+            .fold(init, |total, shard| f(total, &lock(shard)))
+    }
+}
+
+// A thread that panicked while holding the lock cannot have left a cache
+// half-updated in a way that matters, so the poison is ignored.
+fn lock(shard: &Mutex<Cache>) -> std::sync::MutexGuard<'_, Cache> {
+    shard.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<S: Stem> Stem for ShardedCachedStemmer<S> {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        let shard = self.shard(word);
+        if let Some(stem) = lock(shard).get(word) {
+            return Ok(stem);
+        }
+        let stem = self.stemmer.stem(word)?;
+        lock(shard).insert(word, &stem);
+        Ok(stem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_cached_stemmer() -> Result<(), StemError> {
+        let stemmer = CachedStemmer::new(Porter, 2);
+        assert_eq!(stemmer.stem("ponies")?, "poni");
+        assert_eq!(stemmer.stem("ponies")?, "poni");
+        assert_eq!(stemmer.stem("cats")?, "cat");
+        assert_eq!(stemmer.stats(), CacheStats { hits: 1, misses: 2 });
+        assert!(stemmer.stem("caf\u{e9}").is_err());
+        assert_eq!(stemmer.len(), 2);
+
+        // ponies was hit, so the clock passes over it and evicts cats.
+        stemmer.stem("hopping")?;
+        stemmer.stem("ponies")?;
+        stemmer.stem("cats")?;
+        assert_eq!(stemmer.stats(), CacheStats { hits: 2, misses: 5 });
+        assert_eq!((stemmer.len(), stemmer.capacity()), (2, 2));
+
+        stemmer.clear();
+        assert!(stemmer.is_empty());
+        assert_eq!(stemmer.stats().hit_rate(), 0.0);
+
+        let uncached = CachedStemmer::new(Porter, 0);
+        uncached.stem("cats")?;
+        uncached.stem("cats")?;
+        assert_eq!(uncached.stats(), CacheStats { hits: 0, misses: 2 });
+        assert!(uncached.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_borrowed_hit() -> Result<(), StemError> {
+        // A hit on a word that is its own stem borrows it.
+        let stemmer = CachedStemmer::new(Porter, 2);
+        stemmer.stem("cat")?;
+        stemmer.stem("ponies")?;
+        assert!(matches!(stemmer.stem("cat")?, Cow::Borrowed("cat")));
+        assert!(matches!(stemmer.stem("ponies")?, Cow::Owned(_)));
+        assert_eq!(stemmer.stats().hits, 2);
+
+        let stemmer = ShardedCachedStemmer::new(Porter, 2, 2);
+        stemmer.stem("cat")?;
+        assert!(matches!(stemmer.stem("cat")?, Cow::Borrowed("cat")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sharded_cached_stemmer() {
+        let stemmer = ShardedCachedStemmer::new(Porter, 10, 4);
+        assert_eq!(stemmer.capacity(), 10);
+        let words = ["connected", "connecting", "ponies", "cats"];
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for word in &words {
+                        assert_eq!(stemmer.stem(word).unwrap(), Porter.stem(word).unwrap());
+                    }
+                });
+            }
+        });
+        let stats = stemmer.stats();
+        assert_eq!(stats.hits + stats.misses, 16);
+        assert!(stats.misses >= 4);
+        assert!(stemmer.len() <= 4);
+        stemmer.clear();
+        assert!(stemmer.is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+// Conflation classes: the groups of distinct words that a stemmer collapses
+// into the same stem, for reviewing what it conflates. Classes are sorted
+// largest first, so that overstemming shows at the top.
+//
+// Classes are exported as JSON, an array with one object per line:
+//
+//     [
+//     {"stem": "connect", "size": 3, "words": ["connected", "connecting", "connection"]},
+//     {"stem": "poni", "size": 2, "words": ["ponies", "pony"]}
+//     ]
+//
+// or as TSV, under a header line, with the words separated by spaces:
+//
+//     stem	size	words
+//     connect	3	connected connecting connection
+
+use std::io::{self, BufRead, Write};
+
+use crate::index::StemIndex;
+
+/// The distinct words that have the same stem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflationClass {
+    pub stem: String,
+    /// The words, lowercased and sorted.
+    pub words: Vec<String>,
+}
+
+impl ConflationClass {
+    /// The number of words in the class.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// The conflation classes of the words in `index`, largest first, and classes
+/// of the same size by stem.
+pub fn classes(index: &StemIndex) -> Vec<ConflationClass> {
+    let mut classes: Vec<ConflationClass> = index
+        .stems()
+        .map(|stem| {
+            let mut words: Vec<String> = index
+                .forms(stem)
+                .map(|(word, _)| word.to_lowercase())
+                .collect();
+            words.sort_unstable();
+            words.dedup();
+            ConflationClass {
+                stem: stem.to_string(),
+                words,
+            }
+        })
+        .collect();
+    classes.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.stem.cmp(&b.stem)));
+    classes
+}
+
+/// Reads a corpus from `reader`, splitting it into tokens like `tokenize`, and
+/// gives the conflation classes of the tokens under the Porter stemmer. Tokens
+/// that cannot be stemmed are left out.
+pub fn from_corpus<R: BufRead>(reader: R) -> io::Result<Vec<ConflationClass>> {
+    let mut index = StemIndex::new();
+    add_corpus(&mut index, reader)?;
+    Ok(classes(&index))
+}
+
+/// Reads a corpus from `reader` like `from_corpus`, adding its tokens to
+/// `index`. Bytes that are not UTF-8 are replaced, so the tokens with them
+/// are left out.
+pub fn add_corpus<R: BufRead>(index: &mut StemIndex, mut reader: R) -> io::Result<()> {
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        index.add_text(&String::from_utf8_lossy(&line));
+        line.clear();
+    }
+    Ok(())
+}
+
+/// Writes `classes` as a JSON array.
+pub fn write_json<W: Write>(classes: &[ConflationClass], mut out: W) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, class) in classes.iter().enumerate() {
+        write!(out, "{{\"stem\": ")?;
+        write_json_string(&mut out, &class.stem)?;
+        write!(out, ", \"size\": {}, \"words\": [", class.len())?;
+        for (j, word) in class.words.iter().enumerate() {
+            if j > 0 {
+                write!(out, ", ")?;
+            }
+            write_json_string(&mut out, word)?;
+        }
+        let separator = if i + 1 < classes.len() { "," } else { "" };
+        writeln!(out, "]}}{}", separator)?;
+    }
+    writeln!(out, "]")
+}
+
+/// Writes `classes` as TSV, one class per line under a header.
+pub fn write_tsv<W: Write>(classes: &[ConflationClass], mut out: W) -> io::Result<()> {
+    writeln!(out, "stem\tsize\twords")?;
+    for class in classes {
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            class.stem,
+            class.len(),
+            class.words.join(" ")
+        )?;
+    }
+    Ok(())
+}
+
+fn write_json_string<W: Write>(out: &mut W, text: &str) -> io::Result<()> {
+    write!(out, "\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => write!(out, "\\\"")?,
+            '\\' => write!(out, "\\\\")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", u32::from(c))?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    write!(out, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &str = "Connected, connecting\nconnection. Ponies and a pony\n";
+
+    #[test]
+    fn test_classes() -> io::Result<()> {
+        let classes = from_corpus(CORPUS.as_bytes())?;
+        let sizes: Vec<(&str, usize)> =
+            classes.iter().map(|c| (c.stem.as_str(), c.len())).collect();
+        assert_eq!(sizes, [("connect", 3), ("poni", 2), ("a", 1), ("and", 1)]);
+        assert_eq!(classes[1].words, ["ponies", "pony"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_export() -> io::Result<()> {
+        let classes = &from_corpus(CORPUS.as_bytes())?[1..3];
+        let mut json = Vec::new();
+        write_json(classes, &mut json)?;
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "[\n\
+             {\"stem\": \"poni\", \"size\": 2, \"words\": [\"ponies\", \"pony\"]},\n\
+             {\"stem\": \"a\", \"size\": 1, \"words\": [\"a\"]}\n\
+             ]\n"
+        );
+        let mut tsv = Vec::new();
+        write_tsv(classes, &mut tsv)?;
+        assert_eq!(
+            String::from_utf8(tsv).unwrap(),
+            "stem\tsize\twords\nponi\t2\tponies pony\na\t1\ta\n"
+        );
+
+        let mut json = Vec::new();
+        write_json(&[], &mut json)?;
+        assert_eq!(json, b"[\n]\n");
+        let mut escaped = Vec::new();
+        write_json_string(&mut escaped, "a\"b\\c\n")?;
+        assert_eq!(escaped, b"\"a\\\"b\\\\c\\u000a\"");
+        Ok(())
+    }
+}
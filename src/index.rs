@@ -0,0 +1,302 @@
+// A reverse index from stems to the words they came from, for showing a
+// readable word in place of a stem: `connect` for connected, connecting and
+// connection.
+//
+// The index is saved as text, one line per stem and word:
+//
+//     connect	connected	12
+//     connect	connection	3
+//
+// with the stem, the word and the number of times it was seen separated by
+// tabs. Lines are written sorted by stem and word. Blank lines and lines
+// starting with `#` are skipped when reading.
+//
+// A backslash, tab, newline or carriage return in a stem or word is written as
+// `\\`, `\t`, `\n` or `\r`, and a `#` at the start of a stem as `\#`, so any
+// stem and word can be read back.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::tokenize::stem_text;
+use crate::{stem_str, Stem, StemError};
+
+/// The words seen for each stem, with how often each was seen.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StemIndex {
+    stems: HashMap<String, HashMap<String, u64>>,
+}
+
+impl StemIndex {
+    pub fn new() -> StemIndex {
+        StemIndex::default()
+    }
+
+    /// Stems `word` like `stem_str` and records it under its stem.
+    pub fn stem<'a>(&mut self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        let stem = stem_str(word)?;
+        self.insert(&stem, word, 1);
+        Ok(stem)
+    }
+
+    /// Stems `word` with `stemmer` and records it under its stem.
+    pub fn stem_with<'a, S: Stem>(
+        &mut self,
+        stemmer: &S,
+        word: &'a str,
+    ) -> Result<Cow<'a, str>, StemError> {
+        let stem = stemmer.stem(word)?;
+        self.insert(&stem, word, 1);
+        Ok(stem)
+    }
+
+    /// Splits `text` into tokens like `tokenize::stem_text` and records every
+    /// token that can be stemmed. Tokens that cannot, such as those with a
+    /// non-ASCII letter, are left out.
+    pub fn add_text(&mut self, text: &str) {
+        for (token, stem) in stem_text(text) {
+            if let Ok(stem) = stem {
+                self.insert(&stem, token.text, 1);
+            }
+        }
+    }
+
+    /// Records `count` occurrences of `word` under `stem`. The stem is
+    /// lowercased, and the word is recorded trimmed but otherwise as it is, so
+    /// `Apple` and `apple` are different words of the stem `appl`.
+    pub fn insert(&mut self, stem: &str, word: &str, count: u64) {
+        let forms = self.stems.entry(stem.to_lowercase()).or_default();
+        *forms.entry(word.trim().to_string()).or_insert(0) += count;
+    }
+
+    /// The number of stems in the index.
+    pub fn len(&self) -> usize {
+        self.stems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stems.is_empty()
+    }
+
+    /// The stems in the index, in no particular order.
+    pub fn stems(&self) -> impl Iterator<Item = &str> {
+        self.stems.keys().map(String::as_str)
+    }
+
+    /// The words recorded under `stem` and how often each was seen, in no
+    /// particular order.
+    pub fn forms(&self, stem: &str) -> impl Iterator<Item = (&str, u64)> {
+        self.stems
+            .get(stem)
+            .into_iter()
+            .flatten()
+            .map(|(form, &count)| (form.as_str(), count))
+    }
+
+    /// The word seen most often for `stem`. Ties go to the shorter word, then
+    /// to the first in alphabetical order.
+    pub fn most_frequent(&self, stem: &str) -> Option<&str> {
+        self.forms(stem)
+            .min_by(|a, b| {
+                b.1.cmp(&a.1)
+                    .then(a.0.len().cmp(&b.0.len()))
+                    .then(a.0.cmp(b.0))
+            })
+            .map(|(form, _)| form)
+    }
+
+    /// The shortest word seen for `stem`. Ties go to the more frequent word,
+    /// then to the first in alphabetical order.
+    pub fn shortest(&self, stem: &str) -> Option<&str> {
+        self.forms(stem)
+            .min_by(|a, b| {
+                a.0.len()
+                    .cmp(&b.0.len())
+                    .then(b.1.cmp(&a.1))
+                    .then(a.0.cmp(b.0))
+            })
+            .map(|(form, _)| form)
+    }
+
+    /// Writes the index in its text format.
+    pub fn write_to<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let mut lines: Vec<(&str, &str, u64)> = self
+            .stems
+            .iter()
+            .flat_map(|(stem, forms)| {
+                forms
+                    .iter()
+                    .map(move |(form, &count)| (stem.as_str(), form.as_str(), count))
+            })
+            .collect();
+        lines.sort_unstable();
+        for (stem, form, count) in lines {
+            // A line starting with `#` would be read as a comment.
+            let hash = if stem.starts_with('#') { "\\" } else { "" };
+            writeln!(out, "{}{}\t{}\t{}", hash, escape(stem), escape(form), count)?;
+        }
+        out.flush()
+    }
+
+    /// Reads an index written by `write_to`. Counts for the same stem and word
+    /// on several lines are added up.
+    pub fn read_from<R: BufRead>(reader: R) -> io::Result<StemIndex> {
+        let mut index = StemIndex::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}, got {:?}", number + 1, message, line),
+                )
+            };
+            let fields: Vec<&str> = line.split('\t').collect();
+            let entry = match fields[..] {
+                [stem, form, count] => count.parse().ok().map(|count| (stem, form, count)),
+                _ => None,
+            };
+            let (stem, form, count) = entry
+                .ok_or_else(|| invalid("expected a stem, a word and a count separated by tabs"))?;
+            match (unescape(stem), unescape(form)) {
+                (Some(stem), Some(form)) => index.insert(&stem, &form, count),
+                _ => {
+                    return Err(invalid(
+                        "expected only \\\\, \\t, \\n, \\r or \\# after a backslash",
+                    ))
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    /// Saves the index to the file at `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    /// Loads an index saved with `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<StemIndex> {
+        StemIndex::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+// Escapes a stem or word for the text format.
+fn escape(field: &str) -> Cow<'_, str> {
+    if !field.contains(&['\\', '\t', '\n', '\r'][..]) {
+        return Cow::Borrowed(field);
+    }
+    let mut escaped = String::with_capacity(field.len() + 2);
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+// Undoes `escape`, or gives `None` for a backslash not followed by one of the
+// letters it writes.
+fn unescape(field: &str) -> Option<Cow<'_, str>> {
+    if !field.contains('\\') {
+        return Some(Cow::Borrowed(field));
+    }
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                't' => '\t',
+                'n' => '\n',
+                'r' => '\r',
+                '#' => '#',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    Some(Cow::Owned(unescaped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(words: &[&str]) -> StemIndex {
+        let mut index = StemIndex::new();
+        for word in words {
+            index.stem(word).unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn test_representatives() {
+        let index = index(&[
+            "connected",
+            "Connecting",
+            "connection",
+            "connecting",
+            "connects",
+            "connecting",
+            "ponies",
+        ]);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.most_frequent("connect"), Some("connecting"));
+        assert_eq!(index.shortest("connect"), Some("connects"));
+        assert_eq!(index.forms("connect").count(), 5);
+        assert_eq!(index.most_frequent("poni"), Some("ponies"));
+        assert_eq!(index.most_frequent("gener"), None);
+
+        let mut index = StemIndex::new();
+        index.add_text("Ponies, caf\u{e9}s and pony-rides.");
+        let mut stems: Vec<&str> = index.stems().collect();
+        stems.sort_unstable();
+        assert_eq!(stems, ["and", "poni", "ride"]);
+        assert_eq!(index.forms("poni").count(), 2);
+
+        let mut index = StemIndex::new();
+        index.insert("Appl", "Apple", 1);
+        index.insert("appl", " apple", 2);
+        let mut forms: Vec<(&str, u64)> = index.forms("appl").collect();
+        forms.sort_unstable();
+        assert_eq!(forms, [("Apple", 1), ("apple", 2)]);
+    }
+
+    #[test]
+    fn test_text_format() -> io::Result<()> {
+        let index = index(&["connected", "connecting", "connected", "ponies"]);
+        let mut text = Vec::new();
+        index.write_to(&mut text)?;
+        assert_eq!(
+            String::from_utf8(text.clone()).unwrap(),
+            "connect\tconnected\t2\nconnect\tconnecting\t1\nponi\tponies\t1\n"
+        );
+        assert_eq!(StemIndex::read_from(&text[..])?, index);
+
+        let error = StemIndex::read_from(&b"# comment\n\nconnect\tconnected\n"[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 3:"));
+
+        let mut index = StemIndex::new();
+        index.insert("#a\\b", "tab\there\nnew\rline", 2);
+        let mut text = Vec::new();
+        index.write_to(&mut text)?;
+        assert_eq!(text, b"\\#a\\\\b\ttab\\there\\nnew\\rline\t2\n");
+        assert_eq!(StemIndex::read_from(&text[..])?, index);
+
+        let error = StemIndex::read_from(&b"connect\tconnect\\ed\t1\n"[..]).unwrap_err();
+        assert!(error.to_string().starts_with("line 1: expected only"));
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+// A dictionary-validated inflectional stemmer in the style of R. Krovetz,
+// "Viewing Morphology as an Inference Process", SIGIR 1993.
+//
+// Only inflectional endings are removed: plural -s, -es and -ies, past tense
+// -ed and -ied, and -ing. Each ending has a few candidate stems, tried in
+// order, and the first one found in the lexicon is the stem:
+//
+//     -ies  -> -y (ponies: pony)
+//     -s    ->    (horses: horse)
+//     -es   ->    (boxes: box)
+//     -ied  -> -y (carried: carry)
+//     -ed   ->    (walked: walk), or -e (changed: change)
+//     -ying -> -ie (dying: die)
+//     -ing  ->    (singing: sing), or -e (changing: change)
+//
+// -ed and -ing also try undoubling a final consonant (stopped: stop), and the
+// -e is only restored after a consonant. It is tried first where Porter's
+// step 1b would restore it, after a short stem ending consonant-vowel-
+// consonant (hoping: hope), and otherwise last. A word that is in the lexicon
+// itself is kept as it is, and a word none of whose candidates is falls back
+// to the Porter algorithm or is returned unchanged.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::{borrow_prefix, stem_str, validate, Stem, StemError, Word};
+
+/// What `Krovetz` gives for a word it cannot stem to one in its lexicon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fallback {
+    /// The lowercased word.
+    Unchanged,
+    /// The Porter stem of the word, as `stem` gives it.
+    Porter,
+}
+
+/// A stemmer that only removes an inflectional ending when what is left is in
+/// its lexicon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Krovetz {
+    lexicon: HashSet<String>,
+    fallback: Fallback,
+}
+
+impl Krovetz {
+    /// A stemmer with the words of `lexicon`, which are matched after trimming
+    /// and lowercasing, falling back to the unchanged word.
+    pub fn new<I, S>(lexicon: I) -> Krovetz
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Krovetz {
+            lexicon: lexicon
+                .into_iter()
+                .map(|word| word.as_ref().trim().to_ascii_lowercase())
+                .collect(),
+            fallback: Fallback::Unchanged,
+        }
+    }
+
+    /// Reads the lexicon from `reader`, one word per line. Blank lines and
+    /// lines starting with `#` are skipped.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Krovetz> {
+        let mut words = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let word = line.trim();
+            if !word.is_empty() && !word.starts_with('#') {
+                words.push(word.to_string());
+            }
+        }
+        Ok(Krovetz::new(words))
+    }
+
+    /// Reads the lexicon from the word list at `path`, in the format read by
+    /// `from_reader`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Krovetz> {
+        Krovetz::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Uses `fallback` for words that cannot be stemmed to a word in the
+    /// lexicon.
+    pub fn fallback(mut self, fallback: Fallback) -> Krovetz {
+        self.fallback = fallback;
+        self
+    }
+
+    pub fn lexicon(&self) -> &HashSet<String> {
+        &self.lexicon
+    }
+
+    /// Stems `word`. The input is validated, trimmed and lowercased the same
+    /// way as for the Porter `stem`.
+    pub fn stem(&self, word: &str) -> Result<String, StemError> {
+        validate(word)?;
+        let word = word.trim().to_ascii_lowercase();
+        if self.lexicon.contains(&word) {
+            return Ok(word);
+        }
+        if let Some(stem) = candidates(&word)
+            .into_iter()
+            .find(|stem| self.lexicon.contains(stem))
+        {
+            return Ok(stem);
+        }
+        match self.fallback {
+            Fallback::Unchanged => Ok(word),
+            Fallback::Porter => stem_str(&word).map(Cow::into_owned),
+        }
+    }
+}
+
+impl Stem for Krovetz {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        Ok(borrow_prefix(word.trim(), &Krovetz::stem(self, word)?))
+    }
+}
+
+// The candidate stems of `word`, most likely first.
+fn candidates(word: &str) -> Vec<String> {
+    let mut stems = Vec::new();
+    let mut add = |base: &str, ending: &str| {
+        if !base.is_empty() {
+            stems.push(format!("{}{}", base, ending));
+        }
+    };
+    if let Some(base) = word.strip_suffix("ies") {
+        add(base, "y");
+    }
+    if let Some(base) = word.strip_suffix('s').filter(|_| !word.ends_with("ss")) {
+        add(base, "");
+        if let Some(base) = base.strip_suffix('e') {
+            add(base, "");
+        }
+    }
+    if let Some(base) = word.strip_suffix("ied") {
+        add(base, "y");
+    }
+    if let Some(base) = word.strip_suffix("ying") {
+        add(base, "ie");
+    }
+    for ending in &["ed", "ing"] {
+        if let Some(base) = word.strip_suffix(ending) {
+            let restore = base.ends_with(|c: char| !is_vowel(c));
+            let short = restore && is_short(base);
+            if short {
+                add(base, "e");
+            }
+            add(base, "");
+            if let Some(undoubled) = undouble(base) {
+                add(undoubled, "");
+            }
+            if restore && !short {
+                add(base, "e");
+            }
+        }
+    }
+    stems
+}
+
+fn is_vowel(letter: char) -> bool {
+    matches!(letter, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+// Whether `base` is a stem Porter's step 1b adds an -e to: (m=1 and *o).
+fn is_short(base: &str) -> bool {
+    let word = Word::from(base);
+    word.measure() == 1 && word.ends_cvc()
+}
+
+// `base` without the second of the two consonants it ends with, if they are
+// the same.
+fn undouble(base: &str) -> Option<&str> {
+    match base.as_bytes() {
+        [.., a, b] if a == b && !is_vowel(char::from(*b)) => Some(&base[..base.len() - 1]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEXICON: &str = "
+        # A few base forms.
+        pony
+        horse
+        box
+        carry
+        hope
+        hop
+        walk
+        stop
+        die
+        sing
+        general
+    ";
+
+    #[test]
+    fn test_stem() -> Result<(), StemError> {
+        let krovetz = Krovetz::from_reader(LEXICON.as_bytes()).unwrap();
+        assert_eq!(krovetz.lexicon().len(), 11);
+        let stems: Vec<String> = [
+            "Ponies", "horses", "boxes", "carried", "hoped", "hopped", "hoping", "walking",
+            "stopped", "dying", "singing", "general",
+        ]
+        .iter()
+        .map(|word| krovetz.stem(word))
+        .collect::<Result<_, _>>()?;
+        assert_eq!(
+            stems,
+            [
+                "pony", "horse", "box", "carry", "hope", "hop", "hope", "walk", "stop", "die",
+                "sing", "general"
+            ]
+        );
+        assert!(matches!(
+            Stem::stem(&krovetz, "walking")?,
+            Cow::Borrowed("walk")
+        ));
+        assert!(matches!(Stem::stem(&krovetz, "hoping")?, Cow::Owned(_)));
+        assert!(krovetz.stem("").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_restored_e() -> Result<(), StemError> {
+        let krovetz = Krovetz::new(["sing", "singe", "swing", "swinge", "hop", "hope", "change"]);
+        let stems: Vec<String> = ["singing", "swinging", "hoping", "hopping", "changed"]
+            .iter()
+            .map(|word| krovetz.stem(word))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(stems, ["sing", "swing", "hope", "hop", "change"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallback() -> Result<(), StemError> {
+        let krovetz = Krovetz::new(["pony"]);
+        assert_eq!(krovetz.stem("Generalizations")?, "generalizations");
+        assert_eq!(krovetz.stem("glass")?, "glass");
+        let krovetz = krovetz.fallback(Fallback::Porter);
+        assert_eq!(krovetz.stem("Generalizations")?, "gener");
+        assert_eq!(krovetz.stem("ponies")?, "pony");
+        Ok(())
+    }
+}
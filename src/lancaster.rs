@@ -0,0 +1,404 @@
+// The Paice/Husk stemmer, developed at Lancaster University and described in
+// C. D. Paice, "Another Stemmer", SIGIR Forum 24 (1990).
+//
+// The stemmer is iterative: it repeatedly applies the first rule for the last
+// letter of the word whose ending matches and whose result is an acceptable
+// stem, until a rule says to stop, none applies or the one that does gives
+// back a form the word has already had, since rules can undo each other. A
+// stem is acceptable if it starts with a vowel and has at least two letters,
+// or starts with a consonant, has at least three letters and has a vowel or y
+// as its second or third letter.
+//
+// Rules are written in Paice's notation. `ai*2.` reads: if the word ends with
+// -ia (the ending is written reversed) and is still intact (*), remove 2
+// letters and stop (.). `sei3y>` removes -ies, appends y and continues (>).
+// A rule that removes nothing and stops, such as `ss0.`, protects the ending.
+
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::rule_stemmer::ParseError;
+use crate::{borrow_prefix, validate, Stem, StemError, MAX_WORD_LEN};
+
+/// The standard rule table of the Lancaster stemmer, in Paice's notation.
+pub const STANDARD: &[&str] = &[
+    "ai*2.",
+    "a*1.",
+    "bb1.",
+    "city3s.",
+    "ci2>",
+    "cn1t>",
+    "dd1.",
+    "dei3y>",
+    "deec2ss.",
+    "dee1.",
+    "de2>",
+    "dooh4>",
+    "e1>",
+    "feil1v.",
+    "fi2>",
+    "gni3>",
+    "gai3y.",
+    "ga2>",
+    "gg1.",
+    "ht*2.",
+    "hsiug5ct.",
+    "hsi3>",
+    "i*1.",
+    "i1y>",
+    "ji1d.",
+    "juf1s.",
+    "ju1d.",
+    "jo1d.",
+    "jeh1r.",
+    "jrev1t.",
+    "jsim2t.",
+    "jn1d.",
+    "j1s.",
+    "lbaifi6.",
+    "lbai4y.",
+    "lba3>",
+    "lbi3.",
+    "lib2l>",
+    "lc1.",
+    "lufi4y.",
+    "luf3>",
+    "lu2.",
+    "lai3>",
+    "lau3>",
+    "la2>",
+    "ll1.",
+    "mui3.",
+    "mu*2.",
+    "msi3>",
+    "mm1.",
+    "nois4j>",
+    "noix4ct.",
+    "noi3>",
+    "nai3>",
+    "na2>",
+    "nee0.",
+    "ne2>",
+    "nn1.",
+    "pihs4>",
+    "pp1.",
+    "re2>",
+    "rae0.",
+    "ra2.",
+    "ro2>",
+    "ru2>",
+    "rr1.",
+    "rt1>",
+    "rei3y>",
+    "sei3y>",
+    "sis2.",
+    "si2>",
+    "ssen4>",
+    "ss0.",
+    "suo3>",
+    "su*2.",
+    "s*1>",
+    "s0.",
+    "tacilp4y.",
+    "ta2>",
+    "tnem4>",
+    "tne3>",
+    "tna3>",
+    "tpir2b.",
+    "tpro2b.",
+    "tcud1.",
+    "tpmus2.",
+    "tpec2iv.",
+    "tulo2v.",
+    "tsis0.",
+    "tsi3>",
+    "tt1.",
+    "uqi3.",
+    "ugo1.",
+    "vis3j>",
+    "vie0.",
+    "vi2>",
+    "ylb1>",
+    "yli3y>",
+    "ylp0.",
+    "yl2>",
+    "ygo1.",
+    "yhp1.",
+    "ymo1.",
+    "ypo1.",
+    "yti3>",
+    "yte3>",
+    "ytl2.",
+    "yrtsi5.",
+    "yra3>",
+    "yro3>",
+    "yfi3.",
+    "ycn2t>",
+    "yca3>",
+    "zi2>",
+    "zy1s.",
+];
+
+/// A rule of the Lancaster stemmer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaiceRule {
+    /// The ending the word must have, in the order it is read, not reversed
+    /// as in Paice's notation.
+    pub ending: Cow<'static, str>,
+    /// The rule only applies to a word no rule has changed yet.
+    pub intact: bool,
+    /// How many letters to remove from the end of the word.
+    pub remove: usize,
+    /// The letters to append once they are removed.
+    pub append: Cow<'static, str>,
+    /// Stemming stops once the rule has applied.
+    pub stop: bool,
+}
+
+impl FromStr for PaiceRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<PaiceRule, String> {
+        let error = || format!("expected a rule like ai*2. or sei3y>, got {:?}", rule);
+        let letters = |text: &str| text.bytes().take_while(u8::is_ascii_lowercase).count();
+        let ending_len = letters(rule);
+        let (ending, rest) = rule.split_at(ending_len);
+        let (intact, rest) = match rest.strip_prefix('*') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        let mut chars = rest.chars();
+        let remove = chars
+            .next()
+            .and_then(|c| c.to_digit(10))
+            .ok_or_else(error)? as usize;
+        let rest = chars.as_str();
+        let (append, stop) = rest.split_at(letters(rest));
+        let stop = match stop {
+            "." => true,
+            ">" => false,
+            _ => return Err(error()),
+        };
+        if ending.is_empty() {
+            return Err(error());
+        }
+        if remove > ending.len() {
+            return Err(format!(
+                "{:?} removes more letters than its ending has",
+                rule
+            ));
+        }
+        Ok(PaiceRule {
+            ending: ending.chars().rev().collect::<String>().into(),
+            intact,
+            remove,
+            append: append.to_string().into(),
+            stop,
+        })
+    }
+}
+
+/// A Lancaster stemmer running a rule table, by default `STANDARD`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lancaster {
+    rules: Vec<PaiceRule>,
+}
+
+impl Default for Lancaster {
+    fn default() -> Lancaster {
+        Lancaster {
+            rules: STANDARD
+                .iter()
+                .map(|rule| rule.parse().expect("invalid standard rule"))
+                .collect(),
+        }
+    }
+}
+
+thread_local! {
+    static LANCASTER: Lancaster = Lancaster::default();
+}
+
+impl Lancaster {
+    pub fn new() -> Lancaster {
+        Lancaster::default()
+    }
+
+    /// Reads a rule table in the format of Paice's rule files: one rule per
+    /// line, each followed by an optional comment, such as
+    /// `ai*2.  { -ia > - if intact }`. Blank lines are skipped, and a line
+    /// `end0.` ends the table.
+    pub fn parse(source: &str) -> Result<Lancaster, ParseError> {
+        let mut rules = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let rule = match line.split_whitespace().next() {
+                Some("end0.") => break,
+                Some(rule) => rule,
+                None => continue,
+            };
+            let rule = rule
+                .parse()
+                .map_err(|message| ParseError::new(index + 1, message))?;
+            rules.push(rule);
+        }
+        Ok(Lancaster { rules })
+    }
+
+    /// Reads a rule table from the file at `path`, as `parse` does. Errors in
+    /// the file are returned as `InvalidData` errors.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Lancaster> {
+        Lancaster::parse(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn rules(&self) -> &[PaiceRule] {
+        &self.rules
+    }
+
+    /// Stems `word` with the rule table. The input is validated, trimmed and
+    /// lowercased the same way as for the Porter `stem`.
+    pub fn stem(&self, word: &str) -> Result<String, StemError> {
+        validate(word)?;
+        let mut word = word.trim().to_ascii_lowercase();
+        let mut intact = true;
+        // The forms the word has had. `s1s>` gives back the word it was given,
+        // and `b1c>` and `c1b>` turn crab into crac and back, so stemming stops
+        // at the first form that repeats. A table that only ever lengthens the
+        // word stops once it is longer than any `stem` accepts.
+        let mut seen = vec![word.clone()];
+        while let Some(rule) = self.rules.iter().find(|rule| {
+            word.ends_with(rule.ending.as_ref())
+                && (intact || !rule.intact)
+                && is_acceptable(&word[..word.len() - rule.remove])
+        }) {
+            word.truncate(word.len() - rule.remove);
+            word.push_str(&rule.append);
+            if rule.stop || seen.contains(&word) || word.len() > MAX_WORD_LEN {
+                break;
+            }
+            seen.push(word.clone());
+            intact = false;
+        }
+        Ok(word)
+    }
+}
+
+impl FromStr for Lancaster {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Lancaster, ParseError> {
+        Lancaster::parse(source)
+    }
+}
+
+impl Stem for Lancaster {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        Ok(borrow_prefix(word.trim(), &Lancaster::stem(self, word)?))
+    }
+}
+
+/// Stems `word` with the standard Lancaster rules.
+pub fn stem(word: &str) -> Result<String, StemError> {
+    LANCASTER.with(|lancaster| lancaster.stem(word))
+}
+
+fn is_vowel(letter: u8) -> bool {
+    matches!(letter, b'a' | b'e' | b'i' | b'o' | b'u' | b'y')
+}
+
+fn is_acceptable(stem: &str) -> bool {
+    match stem.as_bytes() {
+        [first, ..] if is_vowel(*first) => stem.len() >= 2,
+        [_, second, third, ..] => is_vowel(*second) || is_vowel(*third),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule() {
+        assert_eq!(
+            "sei3y>".parse(),
+            Ok(PaiceRule {
+                ending: "ies".into(),
+                intact: false,
+                remove: 3,
+                append: "y".into(),
+                stop: false,
+            })
+        );
+        let rule: PaiceRule = "ai*2.".parse().unwrap();
+        assert!(rule.intact && rule.stop);
+        assert_eq!(rule.ending, "ia");
+        assert!("ai*2".parse::<PaiceRule>().is_err());
+        assert!("*2.".parse::<PaiceRule>().is_err());
+        assert!("ai3.".parse::<PaiceRule>().is_err());
+        assert_eq!(Lancaster::new().rules().len(), STANDARD.len());
+    }
+
+    #[test]
+    fn test_stem() -> Result<(), StemError> {
+        assert_eq!(stem("maximum")?, "maxim");
+        assert_eq!(stem("presumably")?, "presum");
+        assert_eq!(stem("multiply")?, "multiply");
+        assert_eq!(stem("provision")?, "provid");
+        assert_eq!(stem("owed")?, "ow");
+        assert_eq!(stem("ear")?, "ear");
+        assert_eq!(stem("saying")?, "say");
+        assert_eq!(stem("crying")?, "cry");
+        assert_eq!(stem("string")?, "string");
+        assert_eq!(stem("meant")?, "meant");
+        assert_eq!(stem("Cement ")?, "cem");
+        let lancaster = Lancaster::new();
+        assert!(matches!(
+            Stem::stem(&lancaster, "maximum")?,
+            Cow::Borrowed("maxim")
+        ));
+        assert!(matches!(
+            Stem::stem(&lancaster, "provision")?,
+            Cow::Owned(_)
+        ));
+        assert!(stem("").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rule_file() -> Result<(), StemError> {
+        let lancaster = Lancaster::parse(
+            "sei3y>   { -ies > -y }\n\
+             \n\
+             s1.      { -s > - }\n\
+             end0.\n\
+             this is not read",
+        )
+        .unwrap();
+        assert_eq!(lancaster.rules().len(), 2);
+        assert_eq!(lancaster.stem("ponies")?, "pony");
+        assert_eq!(lancaster.stem("cats")?, "cat");
+        assert_eq!(lancaster.stem("running")?, "running");
+
+        let error = Lancaster::parse("s1.\ns1").unwrap_err();
+        assert_eq!(error.line(), 2);
+        // A rule that changes nothing but continues does not loop.
+        assert_eq!("s0>".parse::<Lancaster>().unwrap().stem("cats")?, "cats");
+        assert_eq!("s1s>".parse::<Lancaster>().unwrap().stem("cats")?, "cats");
+        // Nor do rules that undo each other: the stem is the first form that
+        // comes back, whatever the number of rules.
+        let cycle = "b1c>\nc1b>".parse::<Lancaster>().unwrap();
+        assert_eq!(cycle.stem("crab")?, "crab");
+        assert_eq!(cycle.stem("crac")?, "crac");
+        let cycle = "b1c>\nc1d>\nd1b>\nx1.\ny1.".parse::<Lancaster>().unwrap();
+        assert_eq!(cycle.stem("crab")?, "crab");
+        assert_eq!(cycle.stem("crad")?, "crad");
+        // Nor do rules that keep adding letters.
+        let growing = "s0s>".parse::<Lancaster>().unwrap().stem("cats")?;
+        assert!(growing.len() > MAX_WORD_LEN && growing.starts_with("cats"));
+        Ok(())
+    }
+}
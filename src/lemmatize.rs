@@ -0,0 +1,308 @@
+// An English lemmatizer: it maps an inflected word to its dictionary form
+// (ran: run, mice: mouse, hoping: hope) rather than to a stem.
+//
+// Irregular forms are looked up in a table. Regular inflections are undone by
+// rules: plural -s, -es and -ies, past tense -ied, and -ed and -ing, which go
+// through Porter's step 1b and the clean-up that follows it, so that the e
+// and doubled consonants come out right (hoping: hope, stopped: stop). Words
+// of three letters or less only go through the table.
+//
+// Without a dictionary the rules cannot tell every noun from a verb form, so
+// what they leave of an -ed or -ing word is checked: no English word ends in
+// a consonant followed by l or r. Verbs in -le get their e back (handled:
+// handle), and a word such as hundred, whose -ed is not an ending, is kept
+// as it is.
+
+use std::borrow::Cow;
+
+use crate::{
+    borrow_prefix, resolves, stem1b, stem1bresolve, stem_str, validate, Stem, StemError, Word,
+};
+
+/// Irregular forms and their lemmas.
+pub const IRREGULAR: &[(&str, &str)] = &[
+    // Verbs.
+    ("am", "be"),
+    ("are", "be"),
+    ("is", "be"),
+    ("was", "be"),
+    ("were", "be"),
+    ("been", "be"),
+    ("being", "be"),
+    ("has", "have"),
+    ("had", "have"),
+    ("does", "do"),
+    ("did", "do"),
+    ("done", "do"),
+    ("went", "go"),
+    ("gone", "go"),
+    ("goes", "go"),
+    ("ran", "run"),
+    ("began", "begin"),
+    ("begun", "begin"),
+    ("bought", "buy"),
+    ("brought", "bring"),
+    ("built", "build"),
+    ("came", "come"),
+    ("caught", "catch"),
+    ("chose", "choose"),
+    ("chosen", "choose"),
+    ("drew", "draw"),
+    ("drawn", "draw"),
+    ("drove", "drive"),
+    ("driven", "drive"),
+    ("ate", "eat"),
+    ("eaten", "eat"),
+    ("fell", "fall"),
+    ("fallen", "fall"),
+    ("felt", "feel"),
+    ("fought", "fight"),
+    ("found", "find"),
+    ("flew", "fly"),
+    ("flown", "fly"),
+    ("forgot", "forget"),
+    ("forgotten", "forget"),
+    ("gave", "give"),
+    ("given", "give"),
+    ("got", "get"),
+    ("gotten", "get"),
+    ("grew", "grow"),
+    ("grown", "grow"),
+    ("heard", "hear"),
+    ("held", "hold"),
+    ("kept", "keep"),
+    ("knew", "know"),
+    ("known", "know"),
+    ("led", "lead"),
+    ("left", "leave"),
+    ("lost", "lose"),
+    ("made", "make"),
+    ("meant", "mean"),
+    ("met", "meet"),
+    ("paid", "pay"),
+    ("rode", "ride"),
+    ("ridden", "ride"),
+    ("rose", "rise"),
+    ("risen", "rise"),
+    ("said", "say"),
+    ("sang", "sing"),
+    ("sung", "sing"),
+    ("sat", "sit"),
+    ("saw", "see"),
+    ("seen", "see"),
+    ("sent", "send"),
+    ("sold", "sell"),
+    ("slept", "sleep"),
+    ("spoke", "speak"),
+    ("spoken", "speak"),
+    ("spent", "spend"),
+    ("stood", "stand"),
+    ("stole", "steal"),
+    ("stolen", "steal"),
+    ("swam", "swim"),
+    ("swum", "swim"),
+    ("taught", "teach"),
+    ("thought", "think"),
+    ("threw", "throw"),
+    ("thrown", "throw"),
+    ("told", "tell"),
+    ("took", "take"),
+    ("taken", "take"),
+    ("understood", "understand"),
+    ("woke", "wake"),
+    ("woken", "wake"),
+    ("won", "win"),
+    ("wore", "wear"),
+    ("worn", "wear"),
+    ("wrote", "write"),
+    ("written", "write"),
+    ("dying", "die"),
+    ("lying", "lie"),
+    ("tying", "tie"),
+    // Nouns.
+    ("men", "man"),
+    ("women", "woman"),
+    ("children", "child"),
+    ("people", "person"),
+    ("mice", "mouse"),
+    ("lice", "louse"),
+    ("geese", "goose"),
+    ("feet", "foot"),
+    ("teeth", "tooth"),
+    ("oxen", "ox"),
+    ("knives", "knife"),
+    ("wives", "wife"),
+    ("lives", "life"),
+    ("leaves", "leaf"),
+    ("halves", "half"),
+    ("selves", "self"),
+    ("shelves", "shelf"),
+    ("wolves", "wolf"),
+    ("heroes", "hero"),
+    ("potatoes", "potato"),
+    ("tomatoes", "tomato"),
+    ("analyses", "analysis"),
+    ("crises", "crisis"),
+    ("theses", "thesis"),
+    ("criteria", "criterion"),
+    ("phenomena", "phenomenon"),
+    ("cacti", "cactus"),
+    ("fungi", "fungus"),
+    ("nuclei", "nucleus"),
+    ("radii", "radius"),
+    ("indices", "index"),
+    ("matrices", "matrix"),
+    ("appendices", "appendix"),
+    // Adjectives and adverbs.
+    ("better", "good"),
+    ("best", "good"),
+    ("worse", "bad"),
+    ("worst", "bad"),
+    ("less", "little"),
+    ("least", "little"),
+    ("more", "much"),
+    ("most", "much"),
+    ("farther", "far"),
+    ("farthest", "far"),
+    ("further", "far"),
+    ("furthest", "far"),
+    ("elder", "old"),
+    ("eldest", "old"),
+    // Words that only look inflected.
+    ("nothing", "nothing"),
+    ("something", "something"),
+    ("anything", "anything"),
+    ("everything", "everything"),
+    ("during", "during"),
+    ("morning", "morning"),
+    ("evening", "evening"),
+    ("ceiling", "ceiling"),
+    ("wedding", "wedding"),
+    ("pudding", "pudding"),
+    ("naked", "naked"),
+    ("wicked", "wicked"),
+    ("beloved", "beloved"),
+];
+
+// Plurals that lose -es rather than -s.
+const ES_PLURALS: &[&str] = &["sses", "xes", "ches", "shes", "zzes"];
+
+/// The lemmatizer, as run by `lemmatize`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Lemmatizer;
+
+impl Stem for Lemmatizer {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        Ok(borrow_prefix(word.trim(), &lemmatize(word)?))
+    }
+}
+
+/// Lemmatizes words, then gives their Porter stem, so that irregular forms
+/// conflate with the regular ones: `mice` and `mouse` both stem to `mous`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LemmaPorter;
+
+impl Stem for LemmaPorter {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        let lemma = lemmatize(word)?;
+        Ok(borrow_prefix(word.trim(), &stem_str(&lemma)?))
+    }
+}
+
+/// Gives the lemma of `word`. The input is validated, trimmed and lowercased
+/// the same way as for the Porter `stem`.
+pub fn lemmatize(word: &str) -> Result<String, StemError> {
+    validate(word)?;
+    let word = word.trim().to_ascii_lowercase();
+    if let Some(&(_, lemma)) = IRREGULAR.iter().find(|(form, _)| *form == word) {
+        return Ok(lemma.to_string());
+    }
+    if word.len() <= 3 {
+        return Ok(word);
+    }
+    if let Some(base) = word
+        .strip_suffix("ies")
+        .or_else(|| word.strip_suffix("ied"))
+    {
+        // ponies: pony, but ties: tie.
+        let ending = if base.len() > 1 { "y" } else { "ie" };
+        return Ok(format!("{}{}", base, ending));
+    }
+    if word.ends_with('s') {
+        if ES_PLURALS.iter().any(|plural| word.ends_with(plural)) {
+            return Ok(word[..word.len() - 2].to_string());
+        }
+        if word.ends_with("ss") || word.ends_with("us") || word.ends_with("is") {
+            return Ok(word);
+        }
+        return Ok(word[..word.len() - 1].to_string());
+    }
+    let mut my_word = Word::from(word.as_str());
+    if resolves(stem1b(&mut my_word)) {
+        stem1bresolve(&mut my_word);
+        let len = my_word.len();
+        if len >= 2
+            && my_word.is_consonant_at(len - 2)
+            && !matches!(my_word.as_str().as_bytes()[len - 2], b'l' | b'r' | b'w')
+        {
+            match my_word.last() {
+                Some(b'l') => my_word.push(b'e'),
+                Some(b'r') => return Ok(word),
+                _ => {}
+            }
+        }
+    }
+    Ok(my_word.as_str().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lemmatize() -> Result<(), StemError> {
+        let pairs = [
+            ("ran", "run"),
+            ("Better", "good"),
+            ("mice", "mouse"),
+            ("ponies", "pony"),
+            ("ties", "tie"),
+            ("carried", "carry"),
+            ("churches", "church"),
+            ("horses", "horse"),
+            ("glass", "glass"),
+            ("hoping", "hope"),
+            ("hopping", "hop"),
+            ("stopped", "stop"),
+            ("agreed", "agree"),
+            ("related", "relate"),
+            ("falling", "fall"),
+            ("sing", "sing"),
+            ("run", "run"),
+            ("handled", "handle"),
+            ("entered", "enter"),
+            ("hundred", "hundred"),
+            ("sacred", "sacred"),
+            ("need", "need"),
+            ("nothing", "nothing"),
+            ("bed", "bed"),
+        ];
+        for (word, lemma) in &pairs {
+            assert_eq!(lemmatize(word)?, *lemma, "{}", word);
+        }
+        assert!(lemmatize("").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lemma_porter() -> Result<(), StemError> {
+        for words in &[["mice", "mouse"], ["ran", "running"], ["geese", "goose"]] {
+            assert_eq!(LemmaPorter.stem(words[0])?, LemmaPorter.stem(words[1])?);
+        }
+        assert_eq!(LemmaPorter.stem("Generalizations")?, "gener");
+        assert!(matches!(LemmaPorter.stem("running")?, Cow::Borrowed("run")));
+        assert!(matches!(LemmaPorter.stem("mice")?, Cow::Owned(_)));
+        assert!(matches!(Lemmatizer.stem("cats")?, Cow::Borrowed("cat")));
+        Ok(())
+    }
+}
@@ -1,525 +1,961 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::error;
 use std::fmt;
-use std::iter::Skip;
-use std::str::Chars;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::thread::LocalKey;
+
+// Lets the code generated from Snowball sources name the crate the same way
+// here as in the crates that depend on it.
+extern crate self as stemmer;
+
+pub mod cache;
+pub mod conflation;
+pub mod evaluation;
+pub mod index;
+pub mod krovetz;
+pub mod lancaster;
+pub mod lemmatize;
+pub mod lovins;
+pub mod porter2;
+pub mod rule_stemmer;
+pub mod rules;
+pub mod s_stemmer;
+pub mod snowball;
+pub mod tokenize;
+mod trace;
+
+use rules::RuleTables;
+
+pub use trace::{Trace, TraceStep};
+
+/// The longest input, in bytes, that the stemmers accept.
+pub const MAX_WORD_LEN: usize = 1024;
 
 type Result<T, StemError> = std::result::Result<T, StemError>;
-#[derive(Debug, Clone)]
-pub struct StemError;
+
+/// Why an input was rejected.
+///
+/// More kinds may be added, so a `match` on it needs a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StemErrorKind {
+    /// The input contains a character outside of ASCII.
+    NonAscii,
+    /// The input is the empty string.
+    Empty,
+    /// The input consists only of whitespace.
+    WhitespaceOnly,
+    /// The input is longer than `MAX_WORD_LEN` bytes.
+    TooLong,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StemError {
+    kind: StemErrorKind,
+    character: Option<char>,
+    offset: Option<usize>,
+}
+
+impl StemError {
+    fn new(kind: StemErrorKind) -> StemError {
+        StemError {
+            kind,
+            character: None,
+            offset: None,
+        }
+    }
+
+    fn at(kind: StemErrorKind, character: char, offset: usize) -> StemError {
+        StemError {
+            kind,
+            character: Some(character),
+            offset: Some(offset),
+        }
+    }
+
+    /// Why the input was rejected.
+    pub fn kind(&self) -> StemErrorKind {
+        self.kind
+    }
+
+    /// The offending character: the first non-ASCII one, or the first one past
+    /// `MAX_WORD_LEN`. `None` for an empty or blank input.
+    pub fn character(&self) -> Option<char> {
+        self.character
+    }
+
+    /// The byte offset of `character` in the input, or `None` when there is
+    /// no offending character.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+}
 
 impl fmt::Display for StemError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Invalid Input")
+        match (self.kind, self.character, self.offset) {
+            (StemErrorKind::NonAscii, Some(character), Some(offset)) => write!(
+                f,
+                "Invalid Input: non-ASCII character {:?} at byte {}",
+                character, offset
+            ),
+            (StemErrorKind::Empty, _, _) => write!(f, "Invalid Input: empty input"),
+            (StemErrorKind::WhitespaceOnly, _, _) => {
+                write!(f, "Invalid Input: input is only whitespace")
+            }
+            (StemErrorKind::TooLong, _, _) => write!(
+                f,
+                "Invalid Input: input is longer than {} bytes",
+                MAX_WORD_LEN
+            ),
+            _ => write!(f, "Invalid Input"),
+        }
     }
 }
+
+impl error::Error for StemError {}
+
 // A \consonant\ in a word is a letter other than A, E, I, O or U, and other
 // than Y preceded by a consonant. (The fact that the term `consonant' is
 // defined to some extent in terms of itself does not make it ambiguous.) So in
 // TOY the consonants are T and Y, and in SYZYGY they are S, Z and G. If a
 // letter is not a consonant it is a \vowel\.
 
-fn is_consonant(current: Option<char>, previous: Option<char>) -> bool {
+// `previous` is whether the letter before `current` is a consonant, or `None`
+// at the start of the word, where a Y is a consonant.
+fn is_consonant(current: Option<char>, previous: Option<bool>) -> bool {
     match current {
         Some('a') | Some('e') | Some('i') | Some('o') | Some('u') => false,
-        Some('y') => match previous {
-            Some('a') | Some('e') | Some('i') | Some('o') | Some('u') => true,
-            _ => false,
-        },
+        Some('y') => !previous.unwrap_or(false),
         _ => true,
     }
 }
 
-fn has_vowel(word: &str) -> bool {
-    has_vowel_with_limit(&word, word.len())
+// A letter of a `Word`. `measure` and `has_vowel` describe the prefix of the
+// word that ends with this letter, so checking the stem left by removing a
+// suffix is a lookup rather than a rescan of the word.
+#[derive(Debug, Clone, Copy)]
+struct Letter {
+    consonant: bool,
+    measure: usize,
+    has_vowel: bool,
+}
+
+// An ASCII word being stemmed together with its consonant mask. The mask is
+// computed once as the word is loaded and extended as the steps append
+// letters; truncating the word leaves the mask of the remaining prefix valid.
+#[derive(Debug, Default, Clone)]
+struct Word {
+    text: String,
+    letters: Vec<Letter>,
 }
 
-fn has_vowel_with_limit(word: &str, max: usize) -> bool {
-    let my_chars = word.chars();
-    let mut previous: Option<char>;
-    let mut current: Option<char> = None;
-    let mut count: usize = 1;
-    for my_char in my_chars {
-        if count > max {
-            return false;
+impl Word {
+    fn set(&mut self, word: &str) {
+        self.text.clear();
+        self.text.push_str(word);
+        self.text.make_ascii_lowercase();
+        self.letters.clear();
+        for index in 0..self.text.len() {
+            self.mark(index);
         }
-        previous = current;
-        current = Some(my_char);
-        if !is_consonant(current, previous) {
-            return true;
+    }
+
+    fn push(&mut self, letter: u8) {
+        self.text.push(char::from(letter));
+        self.mark(self.text.len() - 1);
+    }
+
+    // Extends the mask with the letter at `index`, which must be the first
+    // letter not yet in it.
+    fn mark(&mut self, index: usize) {
+        let current = char::from(self.text.as_bytes()[index]);
+        let previous = self.letters.last().map(|last| last.consonant);
+        let consonant = is_consonant(Some(current), previous);
+        let (measure, has_vowel) = match self.letters.last() {
+            // [C](VC){m}[V]: m goes up each time a vowel is followed by a
+            // consonant.
+            Some(last) => (
+                last.measure + usize::from(consonant && !last.consonant),
+                last.has_vowel || !consonant,
+            ),
+            None => (0, !consonant),
         };
-        count += 1;
+        self.letters.push(Letter {
+            consonant,
+            measure,
+            has_vowel,
+        });
     }
-    false
-}
 
-fn measure(word: &str) -> usize {
-    measure_with_limit(&word, word.len())
-}
+    fn push_str(&mut self, suffix: &str) {
+        for letter in suffix.bytes() {
+            self.push(letter);
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.text.truncate(len);
+        self.letters.truncate(len);
+    }
+
+    fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    fn ends_with(&self, suffix: &str) -> bool {
+        self.text.ends_with(suffix)
+    }
+
+    fn last(&self) -> Option<u8> {
+        self.text.as_bytes().last().copied()
+    }
 
-fn measure_with_limit(word: &str, max: usize) -> usize {
-    let my_chars = word.chars();
-    let mut previous: Option<char>;
-    let mut current: Option<char> = None;
-    let mut count = 0;
-    let mut current_consonant = false;
-    let mut begin_counting = false;
-    for (position, my_char) in my_chars.enumerate() {
-        if position == max {
-            return count;
+    fn get_char_at_position(&self, position: usize) -> Option<u8> {
+        if position == 0 {
+            return None;
         }
-        previous = current;
-        current = Some(my_char);
-        if !begin_counting {
-            if !is_consonant(current, previous) {
-                begin_counting = true;
-            }
-        } else if current_consonant != is_consonant(current, previous) {
-            current_consonant = !current_consonant;
-            // Only increase count when we go from vowel to consonant
-            if current_consonant {
-                count += 1;
-            }
+        self.text.as_bytes().get(position - 1).copied()
+    }
+
+    fn is_consonant_at(&self, index: usize) -> bool {
+        self.letters[index].consonant
+    }
+
+    #[cfg(test)]
+    fn has_vowel(&self) -> bool {
+        self.has_vowel_with_limit(self.len())
+    }
+
+    fn has_vowel_with_limit(&self, max: usize) -> bool {
+        match max.min(self.len()) {
+            0 => false,
+            max => self.letters[max - 1].has_vowel,
+        }
+    }
+
+    fn measure(&self) -> usize {
+        self.measure_with_limit(self.len())
+    }
+
+    fn measure_with_limit(&self, max: usize) -> usize {
+        match max.min(self.len()) {
+            0 => 0,
+            max => self.letters[max - 1].measure,
         }
     }
-    count
+
+    fn ends_with_double_consonant(&self) -> bool {
+        self.ends_with_double_consonant_with_limit(self.len())
+    }
+
+    fn ends_with_double_consonant_with_limit(&self, max: usize) -> bool {
+        let bytes = self.text.as_bytes();
+        let len = max.min(bytes.len());
+        len >= 2 && bytes[len - 1] == bytes[len - 2] && self.is_consonant_at(len - 1)
+    }
+
+    fn ends_cvc(&self) -> bool {
+        self.ends_cvc_with_limit(self.len())
+    }
+
+    // Consonant-vowel-consonant, where the second consonant is not W, X or Y.
+    fn ends_cvc_with_limit(&self, max: usize) -> bool {
+        let len = max.min(self.len());
+        len >= 3
+            && self.is_consonant_at(len - 1)
+            && !self.is_consonant_at(len - 2)
+            && self.is_consonant_at(len - 3)
+            && !matches!(self.text.as_bytes()[len - 1], b'w' | b'x' | b'y')
+    }
 }
 
-fn get_char_at_position(word: &str, position: usize) -> Option<char> {
-    if position == 0 {
-        return None;
+impl From<&str> for Word {
+    fn from(word: &str) -> Word {
+        let mut my_word = Word::default();
+        my_word.set(word);
+        my_word
     }
-    word.chars().nth(position - 1)
 }
 
-pub fn stem(word: String) -> Result<String, StemError> {
-    for my_char in word.chars() {
-        if !my_char.is_ascii() {
-            return Err(StemError);
+thread_local! {
+    static STEMMER: RefCell<Stemmer> = RefCell::new(Stemmer::new());
+    static STEP1_STEMMER: RefCell<Stemmer> = RefCell::new(
+        Stemmer::builder().config(PorterConfig::step1()).build()
+    );
+}
+
+pub(crate) fn validate(word: &str) -> Result<(), StemError> {
+    check(word, true)
+}
+
+// `validate` for the stemmers that take words in any script.
+pub(crate) fn validate_unicode(word: &str) -> Result<(), StemError> {
+    check(word, false)
+}
+
+fn check(word: &str, ascii: bool) -> Result<(), StemError> {
+    if word.is_empty() {
+        return Err(StemError::new(StemErrorKind::Empty));
+    }
+    if word.len() > MAX_WORD_LEN {
+        let offset = (0..=MAX_WORD_LEN)
+            .rev()
+            .find(|&i| word.is_char_boundary(i))
+            .unwrap_or(0);
+        let character = word[offset..].chars().next().unwrap_or_default();
+        return Err(StemError::at(StemErrorKind::TooLong, character, offset));
+    }
+    if ascii && !word.is_ascii() {
+        if let Some((offset, character)) = word.char_indices().find(|(_, c)| !c.is_ascii()) {
+            return Err(StemError::at(StemErrorKind::NonAscii, character, offset));
         }
     }
-    if word.len() > 2 {
-        let mut my_word = word.to_lowercase().trim().to_string();
-        my_word = stem1a(my_word);
-        my_word = stem1b(my_word);
-        my_word = stem1c(my_word);
-        my_word = stem2(my_word);
-        my_word = stem3(my_word);
-        my_word = stem4(my_word);
-        my_word = stem5a(my_word);
-        my_word = stem5b(my_word);
-        Ok(my_word)
-    } else {
-        Ok(word)
+    if word.trim().is_empty() {
+        return Err(StemError::new(StemErrorKind::WhitespaceOnly));
     }
+    Ok(())
 }
 
-fn stem1a(mut word: String) -> String {
-    if word.ends_with("sses") || word.ends_with("ies") {
-        word.truncate(word.len() - 2);
-    } else if word.ends_with("ss") {
-    } else if word.ends_with('s') {
-        word.truncate(word.len() - 1);
+pub fn stem(word: String) -> Result<String, StemError> {
+    stem_str(&word).map(Cow::into_owned)
+}
+
+/// Stems `word` without taking ownership of it.
+///
+/// The input is returned borrowed when the stem is a prefix of it (no rule
+/// fired, or the rules only removed a suffix), so an owned `String` is only
+/// allocated when a suffix is actually rewritten or the word needed
+/// lowercasing.
+pub fn stem_str(word: &str) -> Result<Cow<'_, str>, StemError> {
+    stem_local(&STEMMER, word)
+}
+
+// Stems `word` with the thread's copy of `stemmer`.
+fn stem_local<'a>(
+    stemmer: &'static LocalKey<RefCell<Stemmer>>,
+    word: &'a str,
+) -> Result<Cow<'a, str>, StemError> {
+    validate(word)?;
+    if word.len() <= 2 {
+        return Ok(Cow::Borrowed(word));
     }
-    word
+    let trimmed = word.trim();
+    stemmer.with(|stemmer| {
+        let mut stemmer = stemmer.borrow_mut();
+        Ok(borrow_prefix(trimmed, stemmer.stem_buffer(trimmed)))
+    })
 }
 
-fn stem1b(mut word: String) -> String {
-    if word.ends_with("eed") {
-        if measure_with_limit(&word, word.len() - 3) > 0 {
-            word.truncate(word.len() - 1);
-        }
-    } else if word.ends_with("ed") {
-        if has_vowel_with_limit(&word, word.len() - 2) {
-            word.truncate(word.len() - 2);
-            return stem1bresolve(word);
-        }
-    } else if word.ends_with("ing") && has_vowel_with_limit(&word, word.len() - 3) {
-        word.truncate(word.len() - 3);
-        return stem1bresolve(word);
-    }
-    word
-}
-
-fn stem1bresolve(mut word: String) -> String {
-    let mut my_chars: Skip<Chars>;
-    let mut one: Option<char> = None;
-    let mut two: Option<char> = None;
-    if word.len() >= 4 {
-        my_chars = word.chars().skip(word.len() - 4);
-        one = my_chars.next();
-        two = my_chars.next();
-    } else if word.len() == 3 {
-        my_chars = word.chars().skip(word.len() - 3);
-        two = my_chars.next();
-    } else if word.len() == 2 {
-        my_chars = word.chars().skip(word.len() - 2);
-    } else {
-        return word;
-    }
-    let three = my_chars.next();
-    let four = my_chars.next();
-    if word.ends_with("at") || word.ends_with("bl") || word.ends_with("iz") {
-        word.push('e');
-    } else if four == three && is_consonant(three, two) {
-        match three {
-            Some('l') | Some('s') | Some('z') => return word,
-            _ => {
-                word.pop();
-            }
-        }
-    } else if measure(&word) == 1
-        && is_consonant(four, three)
-        && !is_consonant(three, two)
-        && is_consonant(two, one)
-        && !word.ends_with('w')
-        && !word.ends_with('x')
-        && !word.ends_with('y')
-    {
-        word.push('e');
-    }
-    word
-}
-
-fn stem1c(mut word: String) -> String {
-    let l = word.pop();
-    if has_vowel(&word) && l == Some('y') {
-        word.push('i');
+// `stem`, borrowed from `word` if it is a prefix of it.
+fn borrow_prefix<'a>(word: &'a str, stem: &str) -> Cow<'a, str> {
+    if word.starts_with(stem) {
+        Cow::Borrowed(&word[..stem.len()])
     } else {
-        word.push(l.unwrap());
+        Cow::Owned(stem.to_string())
     }
-    word
 }
 
-fn stem2(mut word: String) -> String {
-    let letter = get_char_at_position(&word, word.len() - 1);
-    match letter {
-        Some('a') => {
-            if word.ends_with("ational") && measure_with_limit(&word, word.len() - 7) > 0 {
-                word.truncate(word.len() - 7);
-                word.push_str("ate");
-            } else if word.ends_with("tional") && measure_with_limit(&word, word.len() - 6) > 0 {
-                word.truncate(word.len() - 6);
-                word.push_str("tion");
-            }
-        }
-        Some('c') => {
-            if word.ends_with("enci") && measure_with_limit(&word, word.len() - 4) > 0 {
-                word.truncate(word.len() - 4);
-                word.push_str("ence");
-            } else if word.ends_with("anci") && measure_with_limit(&word, word.len() - 4) > 0 {
-                word.truncate(word.len() - 4);
-                word.push_str("ance");
-            }
-        }
-        Some('e') => {
-            if word.ends_with("izer") && measure_with_limit(&word, word.len() - 4) > 0 {
-                word.truncate(word.len() - 4);
-                word.push_str("ize");
-            }
-        }
-        Some('l') => {
-            if word.ends_with("abli") && measure_with_limit(&word, word.len() - 4) > 0 {
-                word.truncate(word.len() - 4);
-                word.push_str("able");
-            } else if word.ends_with("alli") && measure_with_limit(&word, word.len() - 4) > 0 {
-                word.truncate(word.len() - 4);
-                word.push_str("al");
-            } else if word.ends_with("entli") && measure_with_limit(&word, word.len() - 5) > 0 {
-                word.truncate(word.len() - 5);
-                word.push_str("ent");
-            } else if word.ends_with("ousli") && measure_with_limit(&word, word.len() - 5) > 0 {
-                word.truncate(word.len() - 5);
-                word.push_str("ous");
-            } else if word.ends_with("eli") && measure_with_limit(&word, word.len() - 3) > 0 {
-                word.truncate(word.len() - 3);
-                word.push_str("e");
-            }
-        }
-        Some('o') => {
-            if word.ends_with("ization") && measure_with_limit(&word, word.len() - 7) > 0 {
-                word.truncate(word.len() - 7);
-                word.push_str("ize");
-            } else if word.ends_with("ation") && measure_with_limit(&word, word.len() - 5) > 0 {
-                word.truncate(word.len() - 5);
-                word.push_str("ate");
-            } else if word.ends_with("ator") && measure_with_limit(&word, word.len() - 4) > 0 {
-                word.truncate(word.len() - 4);
-                word.push_str("ate");
-            }
-        }
-        Some('s') => {
-            if word.ends_with("alism") && measure_with_limit(&word, word.len() - 5) > 0 {
-                word.truncate(word.len() - 5);
-                word.push_str("al");
-            } else if word.ends_with("iveness") && measure_with_limit(&word, word.len() - 7) > 0 {
-                word.truncate(word.len() - 7);
-                word.push_str("ive");
-            } else if word.ends_with("fulness") && measure_with_limit(&word, word.len() - 7) > 0 {
-                word.truncate(word.len() - 7);
-                word.push_str("ful");
-            } else if word.ends_with("ousness") && measure_with_limit(&word, word.len() - 7) > 0 {
-                word.truncate(word.len() - 7);
-                word.push_str("ous");
-            }
-        }
-        Some('t') => {
-            if word.ends_with("aliti") && measure_with_limit(&word, word.len() - 5) > 0 {
-                word.truncate(word.len() - 5);
-                word.push_str("al");
-            } else if word.ends_with("iviti") && measure_with_limit(&word, word.len() - 5) > 0 {
-                word.truncate(word.len() - 5);
-                word.push_str("ive");
-            } else if word.ends_with("biliti") && measure_with_limit(&word, word.len() - 6) > 0 {
-                word.truncate(word.len() - 6);
-                word.push_str("ble");
-            }
-        }
-        _ => {}
+/// Stems `word` like `stem`, recording what each step of the pipeline did to
+/// it. The returned `Trace` prints as a table of the steps.
+pub fn stem_traced(word: &str) -> Result<Trace, StemError> {
+    validate(word)?;
+    let mut trace = Trace {
+        word: word.to_string(),
+        stem: word.to_string(),
+        steps: Vec::new(),
+    };
+    if word.len() <= 2 {
+        return Ok(trace);
     }
-    word
+    let mut my_word = Word::from(word.trim());
+    let mut before = my_word.as_str().to_string();
+    let rules = RuleTables::default();
+    let config = PorterConfig::default();
+    run_steps(&mut my_word, &rules, &config, |step, my_word, rule| {
+        let after = my_word.as_str().to_string();
+        trace.steps.push(TraceStep {
+            step,
+            suffix: rule.map(|rule| before[before.len() - rule.suffix_len..].to_string()),
+            replacement: rule.map(|rule| after[after.len() - rule.replacement_len..].to_string()),
+            measure: rule.and_then(|rule| rule.measure),
+            before: std::mem::replace(&mut before, after.clone()),
+            after,
+        });
+    });
+    trace.stem = my_word.as_str().to_string();
+    Ok(trace)
 }
-fn stem3(mut word: String) -> String {
-    let letter = get_char_at_position(&word, word.len() - 1);
-    match letter {
-        Some('a') => {
-            if word.ends_with("ical") && measure_with_limit(&word, word.len() - 4) > 0 {
-                word.truncate(word.len() - 4);
-                word.push_str("ic");
-            }
-        }
-        Some('t') => {
-            if (word.ends_with("icate") && measure_with_limit(&word, word.len() - 5) > 0)
-                || (word.ends_with("iciti") && measure_with_limit(&word, word.len() - 5) > 0)
-            {
-                word.truncate(word.len() - 5);
-                word.push_str("ic");
-            }
-        }
-        Some('u') => {
-            if word.ends_with("ful") && measure_with_limit(&word, word.len() - 3) > 0 {
-                word.truncate(word.len() - 3);
-            }
-        }
-        Some('s') => {
-            if word.ends_with("ness") && measure_with_limit(&word, word.len() - 4) > 0 {
-                word.truncate(word.len() - 4);
-            }
-        }
-        Some('v') => {
-            if word.ends_with("ative") && measure_with_limit(&word, word.len() - 5) > 0 {
-                word.truncate(word.len() - 5);
-            }
-        }
-        Some('z') => {
-            if word.ends_with("alize") && measure_with_limit(&word, word.len() - 5) > 0 {
-                word.truncate(word.len() - 5);
-                word.push_str("al");
-            }
-        }
-        _ => {}
+
+/// A stemming algorithm.
+///
+/// The stemmers of the crate implement it, so code can be written once for
+/// any of them, and a `Box<dyn Stem>` can hold one chosen at run time with
+/// `algorithm`.
+pub trait Stem {
+    /// Stems `word`, borrowing from it where the stem allows.
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError>;
+}
+
+impl<S: Stem + ?Sized> Stem for &S {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        (**self).stem(word)
     }
-    word
 }
 
-fn stem4(mut word: String) -> String {
-    let letter = get_char_at_position(&word, word.len() - 1);
-    match letter {
-        Some('a') => {
-            if word.ends_with("al") && measure_with_limit(&word, word.len() - 2) > 1 {
-                word.truncate(word.len() - 2);
-            }
-        }
-        Some('c') => {
-            if (word.ends_with("ance") && measure_with_limit(&word, word.len() - 4) > 1)
-                || (word.ends_with("ence") && measure_with_limit(&word, word.len() - 4) > 1)
-            {
-                word.truncate(word.len() - 4);
-            }
-        }
-        Some('e') => {
-            if word.ends_with("er") && measure_with_limit(&word, word.len() - 2) > 1 {
-                word.truncate(word.len() - 2);
-            }
+impl<S: Stem + ?Sized> Stem for Box<S> {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        (**self).stem(word)
+    }
+}
+
+/// The Porter algorithm, as run by `stem_str`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Porter;
+
+impl Stem for Porter {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        stem_str(word)
+    }
+}
+
+/// Step 1 of the Porter algorithm alone, which removes plurals, -ed and -ing
+/// and turns a final y into i, for fields where the full pipeline conflates
+/// too much. It runs the steps of `PorterConfig::step1`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PorterStep1;
+
+impl Stem for PorterStep1 {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        stem_local(&STEP1_STEMMER, word)
+    }
+}
+
+/// The names `algorithm` knows, besides `snowball-LANGUAGE` for each of the
+/// `snowball::LANGUAGES`.
+pub const ALGORITHMS: &[&str] = &[
+    "porter",
+    "porter-step1",
+    "porter2",
+    "lovins",
+    "lancaster",
+    "s-stemmer",
+    "lemma",
+    "lemma-porter",
+];
+
+/// The stemmer called `name`, one of `ALGORITHMS` or a `snowball-LANGUAGE`,
+/// or `None` for any other name.
+pub fn algorithm(name: &str) -> Option<Box<dyn Stem + Send + Sync>> {
+    match name {
+        "porter" => Some(Box::new(Porter)),
+        "porter-step1" => Some(Box::new(PorterStep1)),
+        "porter2" => Some(Box::new(porter2::Porter2)),
+        "lovins" => Some(Box::new(lovins::Lovins)),
+        "lancaster" => Some(Box::new(lancaster::Lancaster::new())),
+        "s-stemmer" => Some(Box::new(s_stemmer::SStemmer)),
+        "lemma" => Some(Box::new(lemmatize::Lemmatizer)),
+        "lemma-porter" => Some(Box::new(lemmatize::LemmaPorter)),
+        _ => name
+            .strip_prefix("snowball-")
+            .and_then(snowball::Snowball::new)
+            .map(|stemmer| Box::new(stemmer) as Box<dyn Stem + Send + Sync>),
+    }
+}
+
+/// A reusable Porter stemmer.
+///
+/// The stemmer owns the scratch buffer the steps run in, so once it has seen
+/// a word at least as long as the current one, `stem_into` performs no heap
+/// allocation.
+///
+/// A stemmer made with `Stemmer::builder` can also have its own dictionary of
+/// protected words and overridden stems.
+#[derive(Debug, Default, Clone)]
+pub struct Stemmer {
+    word: Word,
+    protected: HashSet<String>,
+    overrides: HashMap<String, String>,
+    rules: RuleTables,
+    config: PorterConfig,
+}
+
+impl Stemmer {
+    pub fn new() -> Stemmer {
+        Stemmer::default()
+    }
+
+    pub fn builder() -> StemmerBuilder {
+        StemmerBuilder::default()
+    }
+
+    /// The rules the stemmer runs in steps 2 to 4.
+    pub fn rules(&self) -> &RuleTables {
+        &self.rules
+    }
+
+    /// The rules the stemmer runs in steps 2 to 4, to be edited in place.
+    pub fn rules_mut(&mut self) -> &mut RuleTables {
+        &mut self.rules
+    }
+
+    /// The steps the stemmer runs.
+    pub fn config(&self) -> &PorterConfig {
+        &self.config
+    }
+
+    /// The steps the stemmer runs, to be changed in place.
+    pub fn config_mut(&mut self) -> &mut PorterConfig {
+        &mut self.config
+    }
+
+    /// Stems `word` into `out`, replacing its contents. Without a dictionary
+    /// the output is the same as that of `stem`.
+    pub fn stem_into(&mut self, word: &str, out: &mut String) -> Result<(), StemError> {
+        validate(word)?;
+        out.clear();
+        if word.len() <= 2 {
+            self.word.set(word.trim());
+            let stem = self.overrides.get(self.word.as_str());
+            out.push_str(stem.map_or(word, String::as_str));
+        } else {
+            out.push_str(self.stem_buffer(word.trim()));
         }
-        Some('i') => {
-            if word.ends_with("ic") && measure_with_limit(&word, word.len() - 2) > 1 {
-                word.truncate(word.len() - 2);
-            }
+        Ok(())
+    }
+
+    // None of the steps grow the word beyond its original length, so the
+    // buffers never reallocate once they can hold `word`. The dictionary is
+    // looked up by the lowercased word before any step runs.
+    fn stem_buffer(&mut self, word: &str) -> &str {
+        let my_word = &mut self.word;
+        my_word.set(word);
+        if let Some(stem) = self.overrides.get(my_word.as_str()) {
+            return stem;
         }
-        Some('l') => {
-            if (word.ends_with("able") && measure_with_limit(&word, word.len() - 4) > 1)
-                || (word.ends_with("ible") && measure_with_limit(&word, word.len() - 4) > 1)
-            {
-                word.truncate(word.len() - 4);
-            }
+        if !self.protected.contains(my_word.as_str()) {
+            run_steps(my_word, &self.rules, &self.config, |_, _, _| {});
         }
-        Some('n') => {
-            if word.ends_with("ant") && measure_with_limit(&word, word.len() - 3) > 1 {
-                word.truncate(word.len() - 3);
-            } else if word.ends_with("ement") && measure_with_limit(&word, word.len() - 5) > 1 {
-                word.truncate(word.len() - 5);
-            } else if word.ends_with("ment") && measure_with_limit(&word, word.len() - 4) > 1 {
-                word.truncate(word.len() - 4);
-            } else if word.ends_with("ent") && measure_with_limit(&word, word.len() - 3) > 1 {
-                word.truncate(word.len() - 3);
+        my_word.as_str()
+    }
+}
+
+/// Builds a `Stemmer` with a dictionary of words that are not stemmed by the
+/// rules: protected words, which are kept as they are, and words with an
+/// overridden stem. Words are matched after trimming and lowercasing.
+#[derive(Debug, Default, Clone)]
+pub struct StemmerBuilder {
+    protected: HashSet<String>,
+    overrides: HashMap<String, String>,
+    rules: RuleTables,
+    config: PorterConfig,
+}
+
+impl StemmerBuilder {
+    /// Keeps `word` as it is, apart from lowercasing.
+    pub fn protect(mut self, word: &str) -> StemmerBuilder {
+        self.protected.insert(word.trim().to_ascii_lowercase());
+        self
+    }
+
+    /// Gives `stem` as the stem of `word`.
+    pub fn stem_as(mut self, word: &str, stem: &str) -> StemmerBuilder {
+        self.overrides
+            .insert(word.trim().to_ascii_lowercase(), stem.to_string());
+        self
+    }
+
+    /// Runs `rules` in steps 2 to 4 in place of the default tables.
+    pub fn rules(mut self, rules: RuleTables) -> StemmerBuilder {
+        self.rules = rules;
+        self
+    }
+
+    /// Runs the steps enabled in `config` rather than all of them.
+    pub fn config(mut self, config: PorterConfig) -> StemmerBuilder {
+        self.config = config;
+        self
+    }
+
+    /// Adds the words read from `reader`, one per line. A line holding a single
+    /// word protects it, and a line holding a word and a stem, separated by
+    /// whitespace, overrides the stem of the word. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn dictionary<R: BufRead>(mut self, reader: R) -> io::Result<StemmerBuilder> {
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
             }
-        }
-        Some('o') => {
-            if word.ends_with("ion") && measure_with_limit(&word, word.len() - 4) > 1 {
-                let letter2 = get_char_at_position(&word, word.len() - 3);
-                match letter2 {
-                    Some('s') | Some('t') => word.truncate(word.len() - 3),
-                    _ => {}
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            self = match fields[..] {
+                [word] => self.protect(word),
+                [word, stem] => self.stem_as(word, stem),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "line {}: expected a word, or a word and its stem, got {:?}",
+                            number + 1,
+                            line
+                        ),
+                    ))
                 }
-            }
+            };
         }
-        Some('s') => {
-            if word.ends_with("ism") && measure_with_limit(&word, word.len() - 3) > 1 {
-                word.truncate(word.len() - 3);
-            }
+        Ok(self)
+    }
+
+    /// Adds the words in the file at `path`, in the format read by
+    /// `dictionary`.
+    pub fn dictionary_file<P: AsRef<Path>>(self, path: P) -> io::Result<StemmerBuilder> {
+        self.dictionary(BufReader::new(File::open(path)?))
+    }
+
+    pub fn build(self) -> Stemmer {
+        Stemmer {
+            word: Word::default(),
+            protected: self.protected,
+            overrides: self.overrides,
+            rules: self.rules,
+            config: self.config,
         }
-        Some('t') => {
-            if (word.ends_with("ate") && measure_with_limit(&word, word.len() - 3) > 1)
-                || (word.ends_with("iti") && measure_with_limit(&word, word.len() - 3) > 1)
-            {
-                word.truncate(word.len() - 3);
-            }
+    }
+}
+
+// A rule that fired during a step: the last `suffix_len` letters of the word
+// were replaced by the `replacement_len` letters it now ends with. `measure` is
+// the measure of the stem in front of the suffix, for the rules that test it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rule {
+    suffix_len: usize,
+    replacement_len: usize,
+    measure: Option<usize>,
+}
+
+impl Rule {
+    fn new(suffix: &str, replacement: &str, measure: Option<usize>) -> Rule {
+        Rule {
+            suffix_len: suffix.len(),
+            replacement_len: replacement.len(),
+            measure,
         }
-        Some('u') => {
-            if word.ends_with("ous") && measure_with_limit(&word, word.len() - 3) > 1 {
-                word.truncate(word.len() - 3);
-            }
+    }
+}
+
+/// The steps of the Porter algorithm a `Stemmer` runs. The default enables
+/// all of them, which stems the way `stem` does.
+///
+/// Disabling `step1b` also skips the clean-up that follows the removal of -ed
+/// or -ing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PorterConfig {
+    pub step1a: bool,
+    pub step1b: bool,
+    pub step1c: bool,
+    pub step2: bool,
+    pub step3: bool,
+    pub step4: bool,
+    pub step5a: bool,
+    pub step5b: bool,
+}
+
+impl Default for PorterConfig {
+    fn default() -> PorterConfig {
+        PorterConfig {
+            step1a: true,
+            step1b: true,
+            step1c: true,
+            step2: true,
+            step3: true,
+            step4: true,
+            step5a: true,
+            step5b: true,
         }
-        Some('v') => {
-            if word.ends_with("ive") && measure_with_limit(&word, word.len() - 3) > 1 {
-                word.truncate(word.len() - 3);
-            }
+    }
+}
+
+impl PorterConfig {
+    /// Steps 1a to 1c only, which remove plurals, -ed and -ing and turn a
+    /// final y into i.
+    pub fn step1() -> PorterConfig {
+        PorterConfig {
+            step2: false,
+            step3: false,
+            step4: false,
+            step5a: false,
+            step5b: false,
+            ..PorterConfig::default()
         }
-        Some('z') => {
-            if word.ends_with("ize") && measure_with_limit(&word, word.len() - 3) > 1 {
-                word.truncate(word.len() - 3);
-            }
+    }
+}
+
+// Runs the enabled steps over `word`, calling `observe` after each one with
+// its name and the rule it applied, if any. stem1bresolve only runs when
+// stem1b removed -ed or -ing.
+fn run_steps<F>(word: &mut Word, rules: &RuleTables, config: &PorterConfig, mut observe: F)
+where
+    F: FnMut(&'static str, &Word, Option<Rule>),
+{
+    if config.step1a {
+        let rule = stem1a(word);
+        observe("stem1a", word, rule);
+    }
+    if config.step1b {
+        let rule = stem1b(word);
+        observe("stem1b", word, rule);
+        if resolves(rule) {
+            let rule = stem1bresolve(word);
+            observe("stem1bresolve", word, rule);
         }
-        _ => {}
-    }
-    word
-}
-
-fn stem5a(mut word: String) -> String {
-    let y = word.pop().unwrap();
-    let mut my_chars: Skip<Chars>;
-    let mut one: Option<char> = None;
-    let mut two: Option<char> = None;
-    if word.len() >= 4 {
-        my_chars = word.chars().skip(word.len() - 4);
-        one = my_chars.next();
-        two = my_chars.next();
-    } else if word.len() == 3 {
-        my_chars = word.chars().skip(word.len() - 3);
-        two = my_chars.next();
-    } else if word.len() == 2 {
-        my_chars = word.chars().skip(word.len() - 2);
+    }
+    if config.step1c {
+        let rule = stem1c(word);
+        observe("stem1c", word, rule);
+    }
+    if config.step2 {
+        let rule = rules::apply(word, &rules.step2);
+        observe("stem2", word, rule);
+    }
+    if config.step3 {
+        let rule = rules::apply(word, &rules.step3);
+        observe("stem3", word, rule);
+    }
+    if config.step4 {
+        let rule = rules::apply(word, &rules.step4);
+        observe("stem4", word, rule);
+    }
+    if config.step5a {
+        let rule = stem5a(word);
+        observe("stem5a", word, rule);
+    }
+    if config.step5b {
+        let rule = stem5b(word);
+        observe("stem5b", word, rule);
+    }
+}
+
+// -ed and -ing are the only stem1b rules that remove the suffix outright.
+fn resolves(rule: Option<Rule>) -> bool {
+    matches!(
+        rule,
+        Some(Rule {
+            replacement_len: 0,
+            ..
+        })
+    )
+}
+
+// Replaces `suffix` with `replacement` if the word ends with it and the
+// measure of the stem in front of it is greater than `min`.
+fn replace_suffix(word: &mut Word, suffix: &str, replacement: &str, min: usize) -> Option<Rule> {
+    if !word.ends_with(suffix) {
+        return None;
+    }
+    let stem_len = word.len() - suffix.len();
+    let measure = word.measure_with_limit(stem_len);
+    if measure <= min {
+        return None;
+    }
+    word.truncate(stem_len);
+    word.push_str(replacement);
+    Some(Rule::new(suffix, replacement, Some(measure)))
+}
+
+fn stem1a(word: &mut Word) -> Option<Rule> {
+    if word.ends_with("sses") {
+        word.truncate(word.len() - 2);
+        Some(Rule::new("sses", "ss", None))
+    } else if word.ends_with("ies") {
+        word.truncate(word.len() - 2);
+        Some(Rule::new("ies", "i", None))
+    } else if word.ends_with("ss") {
+        None
+    } else if word.ends_with("s") {
+        word.truncate(word.len() - 1);
+        Some(Rule::new("s", "", None))
     } else {
-        word.push(y);
-        return word;
-    }
-    let three = my_chars.next();
-    let four = my_chars.next();
-    if y == 'e' {
-        match measure(&word) {
-            0 => {}
-            1 => {
-                if !is_consonant(two, one) || is_consonant(three, two) || !is_consonant(four, three)
-                {
-                    return word;
-                } else {
-                    match four {
-                        Some('x') | Some('y') | Some('w') => return word,
-                        _ => word.push(y),
-                    }
-                }
+        None
+    }
+}
+
+fn stem1b(word: &mut Word) -> Option<Rule> {
+    if word.ends_with("eed") {
+        replace_suffix(word, "eed", "ee", 0)
+    } else if word.ends_with("ed") {
+        if word.has_vowel_with_limit(word.len() - 2) {
+            word.truncate(word.len() - 2);
+            return Some(Rule::new("ed", "", None));
+        }
+        None
+    } else if word.ends_with("ing") && word.has_vowel_with_limit(word.len() - 3) {
+        word.truncate(word.len() - 3);
+        Some(Rule::new("ing", "", None))
+    } else {
+        None
+    }
+}
+
+fn stem1bresolve(word: &mut Word) -> Option<Rule> {
+    if word.len() < 2 {
+        return None;
+    }
+    for &(suffix, replacement) in &[("at", "ate"), ("bl", "ble"), ("iz", "ize")] {
+        if word.ends_with(suffix) {
+            word.push(b'e');
+            return Some(Rule::new(suffix, replacement, None));
+        }
+    }
+    if word.ends_with_double_consonant() {
+        match word.last() {
+            Some(b'l') | Some(b's') | Some(b'z') => None,
+            _ => {
+                word.truncate(word.len() - 1);
+                Some(Rule {
+                    suffix_len: 2,
+                    replacement_len: 1,
+                    measure: None,
+                })
             }
-            _ => return word,
         }
+    } else if word.measure() == 1 && word.ends_cvc() {
+        word.push(b'e');
+        Some(Rule::new("", "e", Some(1)))
     } else {
-        word.push(y);
+        None
     }
-    word
 }
 
-fn stem5b(mut word: String) -> String {
-    if word.ends_with('l') {
-        let mut my_chars = word.chars().skip(word.len() - 3);
+fn stem1c(word: &mut Word) -> Option<Rule> {
+    if word.ends_with("y") && word.has_vowel_with_limit(word.len() - 1) {
+        word.truncate(word.len() - 1);
+        word.push(b'i');
+        return Some(Rule::new("y", "i", None));
+    }
+    None
+}
 
-        let one = my_chars.next();
-        let two = my_chars.next();
-        let three = my_chars.next();
-        let y = word.pop().unwrap();
-        if !(measure(&word) > 1 && is_consonant(two, one) && is_consonant(three, two)) {
-            word.push(y);
-        }
+fn stem5a(word: &mut Word) -> Option<Rule> {
+    if word.len() < 3 || !word.ends_with("e") {
+        return None;
+    }
+    word.truncate(word.len() - 1);
+    let measure = word.measure();
+    if measure > 1 || (measure == 1 && !word.ends_cvc()) {
+        return Some(Rule::new("e", "", Some(measure)));
+    }
+    word.push(b'e');
+    None
+}
+
+fn stem5b(word: &mut Word) -> Option<Rule> {
+    let len = word.len();
+    if !word.ends_with("ll") {
+        return None;
     }
-    word
+    let measure = word.measure_with_limit(len - 1);
+    if measure > 1 {
+        word.truncate(len - 1);
+        return Some(Rule::new("l", "", Some(measure)));
+    }
+    None
 }
 
 mod tests {
     #[allow(unused_imports)]
     use super::*;
 
+    #[allow(dead_code)]
+    fn apply(step: fn(&mut Word) -> Option<Rule>, word: &str) -> String {
+        let mut my_word = Word::from(word);
+        step(&mut my_word);
+        my_word.as_str().to_string()
+    }
+
     #[test]
     fn test_is_consonant() {
-        assert_eq!(is_consonant(Some('a'), None), false);
-        assert_eq!(is_consonant(Some('e'), None), false);
-        assert_eq!(is_consonant(Some('i'), None), false);
-        assert_eq!(is_consonant(Some('o'), None), false);
-        assert_eq!(is_consonant(Some('u'), None), false);
-        assert_eq!(is_consonant(Some('y'), None), false);
-        assert_eq!(is_consonant(Some('y'), Some('a')), true);
-        assert_eq!(is_consonant(Some('y'), Some('b')), false);
-        assert_eq!(is_consonant(Some('b'), None), true);
-        assert_eq!(is_consonant(Some('c'), None), true);
-        assert_eq!(is_consonant(Some('d'), None), true);
-        assert_eq!(is_consonant(Some('f'), Some('b')), true);
+        assert!(!is_consonant(Some('a'), None));
+        assert!(!is_consonant(Some('e'), None));
+        assert!(!is_consonant(Some('i'), None));
+        assert!(!is_consonant(Some('o'), None));
+        assert!(!is_consonant(Some('u'), None));
+        assert!(is_consonant(Some('y'), None));
+        assert!(is_consonant(Some('y'), Some(false)));
+        assert!(!is_consonant(Some('y'), Some(true)));
+        assert!(is_consonant(Some('b'), None));
+        assert!(is_consonant(Some('c'), None));
+        assert!(is_consonant(Some('d'), None));
+        assert!(is_consonant(Some('f'), Some(true)));
     }
 
     #[test]
     fn test_has_vowel() {
-        assert_eq!(has_vowel(&String::from("test")), true);
-        assert_eq!(has_vowel(&String::from("tst")), false);
-        assert_eq!(has_vowel(&String::from("tsty")), true);
+        assert!(Word::from("test").has_vowel());
+        assert!(!Word::from("tst").has_vowel());
+        assert!(Word::from("tsty").has_vowel());
     }
 
     #[test]
     fn test_measure() {
-        assert_eq!(measure(&String::from("tr")), 0);
-        assert_eq!(measure(&String::from("ee")), 0);
-        assert_eq!(measure(&String::from("tree")), 0);
-        assert_eq!(measure(&String::from("y")), 0);
-        assert_eq!(measure(&String::from("by")), 0);
-        assert_eq!(measure(&String::from("trouble")), 1);
-        assert_eq!(measure(&String::from("oats")), 1);
-        assert_eq!(measure(&String::from("trees")), 1);
-        assert_eq!(measure(&String::from("ivy")), 1);
-        assert_eq!(measure(&String::from("troubles")), 2);
-        assert_eq!(measure(&String::from("private")), 2);
-        assert_eq!(measure(&String::from("oaten")), 2);
-        assert_eq!(measure(&String::from("orrery")), 2);
+        assert_eq!(Word::from("tr").measure(), 0);
+        assert_eq!(Word::from("ee").measure(), 0);
+        assert_eq!(Word::from("tree").measure(), 0);
+        assert_eq!(Word::from("y").measure(), 0);
+        assert_eq!(Word::from("by").measure(), 0);
+        assert_eq!(Word::from("trouble").measure(), 1);
+        assert_eq!(Word::from("oats").measure(), 1);
+        assert_eq!(Word::from("trees").measure(), 1);
+        assert_eq!(Word::from("ivy").measure(), 1);
+        assert_eq!(Word::from("troubles").measure(), 2);
+        assert_eq!(Word::from("private").measure(), 2);
+        assert_eq!(Word::from("oaten").measure(), 2);
+        assert_eq!(Word::from("orrery").measure(), 2);
     }
 
     #[test]
     fn test_measure_with_limit() {
-        assert_eq!(measure_with_limit(&String::from("troubles"), 4), 0);
-        assert_eq!(measure_with_limit(&String::from("troubles"), 5), 1);
-        assert_eq!(measure_with_limit(&String::from("private"), 4), 1);
-        assert_eq!(measure_with_limit(&String::from("oaten"), 5), 2);
-        assert_eq!(measure_with_limit(&String::from("oaten"), 4), 1);
-        assert_eq!(measure_with_limit(&String::from("oaten"), 3), 1);
-        assert_eq!(measure_with_limit(&String::from("oaten"), 2), 0);
-        assert_eq!(measure_with_limit(&String::from("orrery"), 6), 2);
-        assert_eq!(measure_with_limit(&String::from("orrery"), 5), 2);
-        assert_eq!(measure_with_limit(&String::from("orrery"), 4), 1);
-        assert_eq!(measure_with_limit(&String::from("orrery"), 3), 1);
-        assert_eq!(measure_with_limit(&String::from("orrery"), 2), 1);
-        assert_eq!(measure_with_limit(&String::from("orrery"), 1), 0);
+        assert_eq!(Word::from("troubles").measure_with_limit(4), 0);
+        assert_eq!(Word::from("troubles").measure_with_limit(5), 1);
+        assert_eq!(Word::from("private").measure_with_limit(4), 1);
+        assert_eq!(Word::from("oaten").measure_with_limit(5), 2);
+        assert_eq!(Word::from("oaten").measure_with_limit(4), 1);
+        assert_eq!(Word::from("oaten").measure_with_limit(3), 1);
+        assert_eq!(Word::from("oaten").measure_with_limit(2), 0);
+        assert_eq!(Word::from("orrery").measure_with_limit(6), 2);
+        assert_eq!(Word::from("orrery").measure_with_limit(5), 2);
+        assert_eq!(Word::from("orrery").measure_with_limit(4), 1);
+        assert_eq!(Word::from("orrery").measure_with_limit(3), 1);
+        assert_eq!(Word::from("orrery").measure_with_limit(2), 1);
+        assert_eq!(Word::from("orrery").measure_with_limit(1), 0);
+    }
+
+    #[test]
+    fn test_word_mask_follows_edits() {
+        let mut word = Word::from("generalization");
+        word.truncate(word.len() - 7);
+        word.push_str("ize");
+        let fresh = Word::from("generalize");
+        assert_eq!(word.as_str(), fresh.as_str());
+        for max in 0..=word.len() {
+            assert_eq!(word.measure_with_limit(max), fresh.measure_with_limit(max));
+            assert_eq!(
+                word.has_vowel_with_limit(max),
+                fresh.has_vowel_with_limit(max)
+            );
+        }
+        assert!(Word::from("hopp").ends_with_double_consonant());
+        assert!(!Word::from("hoop").ends_with_double_consonant());
+        assert!(Word::from("fil").ends_cvc());
+        assert!(!Word::from("fail").ends_cvc());
     }
 
     #[test]
@@ -541,169 +977,504 @@ mod tests {
     }
 
     #[test]
-    fn test_stem1a() {
+    fn test_stem_str() -> Result<(), StemError> {
+        assert_eq!(stem_str("is")?, "is");
+        assert_eq!(stem_str("caresses")?, "caress");
+        assert_eq!(stem_str("generalization")?, "gener");
+        assert_eq!(stem_str(" Cats ")?, "cat");
+        assert!(matches!(stem_str("caress")?, Cow::Borrowed("caress")));
+        assert!(matches!(stem_str("cats")?, Cow::Borrowed("cat")));
+        assert!(matches!(stem_str("ponies")?, Cow::Borrowed("poni")));
+        assert!(matches!(stem_str("happy")?, Cow::Owned(_)));
+        assert!(matches!(stem_str("Cats")?, Cow::Owned(_)));
+        assert!(stem_str("caf\u{e9}").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stemmer_stem_into() -> Result<(), StemError> {
+        let mut stemmer = Stemmer::new();
+        let mut out = String::new();
+        for word in &[
+            "is",
+            "caresses",
+            "ponies",
+            "generalization",
+            "oscillators",
+            "a",
+            " Trouble ",
+            "dependent",
+        ] {
+            stemmer.stem_into(word, &mut out)?;
+            assert_eq!(out, stem(word.to_string())?);
+        }
+        assert!(stemmer.stem_into("na\u{ef}ve", &mut out).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stemmer_builder() -> Result<(), StemError> {
+        let dictionary = "# brands\nKubernetes\n\naids  aids\nus usa\n";
+        let mut stemmer = Stemmer::builder()
+            .protect("news")
+            .stem_as("Mice", "mouse")
+            .dictionary(dictionary.as_bytes())
+            .unwrap()
+            .build();
+        let mut out = String::new();
+        for &(word, expected) in &[
+            ("News", "news"),
+            ("kubernetes", "kubernetes"),
+            (" aids ", "aids"),
+            ("mice", "mouse"),
+            ("US", "usa"),
+            ("is", "is"),
+            ("connections", "connect"),
+        ] {
+            stemmer.stem_into(word, &mut out)?;
+            assert_eq!(out, expected);
+        }
+
+        let mut stemmer = Stemmer::new();
+        stemmer.stem_into("news", &mut out)?;
+        assert_eq!(out, "new");
+
+        let error = Stemmer::builder()
+            .dictionary("news\nfoo bar baz\n".as_bytes())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 2: "));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stem_trait() {
+        fn stem_all<S: Stem>(stemmer: S, words: &[&str]) -> Vec<String> {
+            words
+                .iter()
+                .map(|word| stemmer.stem(word).unwrap().into_owned())
+                .collect()
+        }
+        let words = ["generously", "Caresses"];
+        assert_eq!(stem_all(Porter, &words), ["gener", "caress"]);
         assert_eq!(
-            String::from(stem1a(String::from("caresses"))),
-            String::from("caress")
+            stem_all(algorithm("porter2").unwrap(), &words),
+            ["generous", "caress"]
         );
+        assert!(matches!(
+            Porter.stem("connected"),
+            Ok(Cow::Borrowed("connect"))
+        ));
+        assert!(Porter.stem("").is_err());
+        for name in ALGORITHMS {
+            assert!(algorithm(name).is_some(), "{}", name);
+        }
+        assert!(algorithm("snowball").is_none());
+    }
+
+    #[test]
+    fn test_porter_step1() {
+        let stems: Vec<_> = ["caresses", "ponies", "hopping", "Happy", "generalization"]
+            .iter()
+            .map(|word| PorterStep1.stem(word).unwrap().into_owned())
+            .collect();
+        assert_eq!(stems, ["caress", "poni", "hop", "happi", "generalization"]);
+        assert!(matches!(PorterStep1.stem("cats"), Ok(Cow::Borrowed("cat"))));
+        assert!(matches!(PorterStep1.stem("happy"), Ok(Cow::Owned(_))));
+        assert!(PorterStep1.stem(" ").is_err());
+    }
+
+    #[test]
+    fn test_porter_config() -> Result<(), StemError> {
+        let mut stemmer = Stemmer::builder()
+            .config(PorterConfig {
+                step4: false,
+                ..PorterConfig::default()
+            })
+            .build();
+        let mut out = String::new();
+        stemmer.stem_into("adjustments", &mut out)?;
+        assert_eq!(out, "adjustment");
+        stemmer.stem_into("probate", &mut out)?;
+        assert_eq!(out, "probat");
+
+        stemmer.config_mut().step4 = true;
+        stemmer.config_mut().step5a = false;
+        stemmer.stem_into("adjustments", &mut out)?;
+        assert_eq!(out, "adjust");
+        stemmer.stem_into("probate", &mut out)?;
+        assert_eq!(out, "probate");
+        Ok(())
+    }
+
+    #[test]
+    fn test_stem_error() {
+        let error = stem_str("caf\u{e9}s").unwrap_err();
+        assert_eq!(error.kind(), StemErrorKind::NonAscii);
+        assert_eq!(error.character(), Some('\u{e9}'));
+        assert_eq!(error.offset(), Some(3));
+        assert_eq!(
+            error.to_string(),
+            "Invalid Input: non-ASCII character '\u{e9}' at byte 3"
+        );
+
+        let error = stem(String::new()).unwrap_err();
+        assert_eq!(error.kind(), StemErrorKind::Empty);
+        assert_eq!(error.character(), None);
+        assert_eq!(error.offset(), None);
+
         assert_eq!(
-            String::from(stem1a(String::from("ponies"))),
-            String::from("poni")
+            stem_str("  ").unwrap_err().kind(),
+            StemErrorKind::WhitespaceOnly
         );
         assert_eq!(
-            String::from(stem1a(String::from("caress"))),
-            String::from("caress")
+            stem_str(" \t\n ").unwrap_err().kind(),
+            StemErrorKind::WhitespaceOnly
         );
+
+        let long = "a".repeat(MAX_WORD_LEN);
+        assert!(stem_str(&long).is_ok());
+        let long = long + "b";
+        let error = stem_str(&long).unwrap_err();
+        assert_eq!(error.kind(), StemErrorKind::TooLong);
+        assert_eq!(error.character(), Some('b'));
+        assert_eq!(error.offset(), Some(MAX_WORD_LEN));
+
+        let mut stemmer = Stemmer::new();
+        let mut out = String::new();
         assert_eq!(
-            String::from(stem1a(String::from("cats"))),
-            String::from("cat")
+            stemmer.stem_into("", &mut out).unwrap_err().kind(),
+            StemErrorKind::Empty
         );
     }
 
     #[test]
-    fn test_stem1b() {
-        assert_eq!(stem1b(String::from("feed")), String::from("feed"));
-        assert_eq!(stem1b(String::from("agreed")), String::from("agree"));
-        assert_eq!(stem1b(String::from("plastered")), String::from("plaster"));
-        assert_eq!(stem1b(String::from("bled")), String::from("bled"));
-        assert_eq!(stem1b(String::from("motoring")), String::from("motor"));
-        assert_eq!(stem1b(String::from("sing")), String::from("sing"));
-        assert_eq!(stem1b(String::from("conflated")), String::from("conflate"));
-        assert_eq!(stem1b(String::from("troubled")), String::from("trouble"));
-        assert_eq!(stem1b(String::from("sized")), String::from("size"));
-        assert_eq!(stem1b(String::from("be")), String::from("be"));
+    fn test_stem_traced() -> Result<(), StemError> {
+        let trace = stem_traced("Hopping")?;
+        assert_eq!(trace.stem, "hop");
+        let steps: Vec<&str> = trace.steps.iter().map(|step| step.step).collect();
+        assert_eq!(
+            steps,
+            [
+                "stem1a",
+                "stem1b",
+                "stem1bresolve",
+                "stem1c",
+                "stem2",
+                "stem3",
+                "stem4",
+                "stem5a",
+                "stem5b"
+            ]
+        );
+        let changes: Vec<_> = trace
+            .changes()
+            .map(|step| {
+                (
+                    step.step,
+                    step.suffix.as_deref(),
+                    step.replacement.as_deref(),
+                    step.before.as_str(),
+                    step.after.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("stem1b", Some("ing"), Some(""), "hopping", "hopp"),
+                ("stem1bresolve", Some("pp"), Some("p"), "hopp", "hop"),
+            ]
+        );
+
+        let trace = stem_traced("generalizations")?;
+        assert_eq!(trace.stem, stem(String::from("generalizations"))?);
+        let changes: Vec<_> = trace
+            .changes()
+            .map(|step| (step.step, step.suffix.as_deref(), step.measure))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("stem1a", Some("s"), None),
+                ("stem2", Some("ization"), Some(3)),
+                ("stem3", Some("alize"), Some(2)),
+                ("stem4", Some("al"), Some(2)),
+            ]
+        );
+        assert_eq!(
+            trace.to_string().lines().take(4).collect::<Vec<_>>(),
+            [
+                "generalizations -> gener",
+                "step    suffix   replacement  m  before           after",
+                "stem1a  s        \"\"           -  generalizations  generalization",
+                "stem1b  -        -            -  generalization   generalization",
+            ]
+        );
+
+        let trace = stem_traced("is")?;
+        assert_eq!(trace.stem, "is");
+        assert!(trace.steps.is_empty());
+        Ok(())
     }
 
     #[test]
-    fn test_stem1bresolve() {
+    fn test_stem1a() {
+        assert_eq!(apply(stem1a, "caresses"), String::from("caress"));
+        assert_eq!(apply(stem1a, "ponies"), String::from("poni"));
+        assert_eq!(apply(stem1a, "caress"), String::from("caress"));
+        assert_eq!(apply(stem1a, "cats"), String::from("cat"));
+    }
+
+    // stem1b followed by stem1bresolve, as run_steps runs them.
+    #[allow(dead_code)]
+    fn stem1b_resolved(word: &mut Word) -> Option<Rule> {
+        let rule = stem1b(word);
+        if resolves(rule) {
+            stem1bresolve(word);
+        }
+        rule
+    }
+
+    #[test]
+    fn test_stem1b() {
+        assert_eq!(apply(stem1b_resolved, "feed"), String::from("feed"));
+        assert_eq!(apply(stem1b_resolved, "agreed"), String::from("agree"));
+        assert_eq!(apply(stem1b_resolved, "plastered"), String::from("plaster"));
+        assert_eq!(apply(stem1b_resolved, "bled"), String::from("bled"));
+        assert_eq!(apply(stem1b_resolved, "motoring"), String::from("motor"));
+        assert_eq!(apply(stem1b_resolved, "sing"), String::from("sing"));
         assert_eq!(
-            stem1bresolve(String::from("conflat")),
+            apply(stem1b_resolved, "conflated"),
             String::from("conflate")
         );
-        assert_eq!(
-            stem1bresolve(String::from("troubl")),
-            String::from("trouble")
-        );
-        assert_eq!(stem1bresolve(String::from("siz")), String::from("size"));
-        assert_eq!(stem1bresolve(String::from("hopp")), String::from("hop"));
-        assert_eq!(stem1bresolve(String::from("tann")), String::from("tan"));
-        assert_eq!(stem1bresolve(String::from("fall")), String::from("fall"));
-        assert_eq!(stem1bresolve(String::from("hiss")), String::from("hiss"));
-        assert_eq!(stem1bresolve(String::from("fail")), String::from("fail"));
-        assert_eq!(stem1bresolve(String::from("fil")), String::from("file"));
+        assert_eq!(apply(stem1b_resolved, "troubled"), String::from("trouble"));
+        assert_eq!(apply(stem1b_resolved, "sized"), String::from("size"));
+        assert_eq!(apply(stem1b_resolved, "be"), String::from("be"));
+    }
+
+    #[test]
+    fn test_stem1bresolve() {
+        assert_eq!(apply(stem1bresolve, "conflat"), String::from("conflate"));
+        assert_eq!(apply(stem1bresolve, "troubl"), String::from("trouble"));
+        assert_eq!(apply(stem1bresolve, "siz"), String::from("size"));
+        assert_eq!(apply(stem1bresolve, "hopp"), String::from("hop"));
+        assert_eq!(apply(stem1bresolve, "tann"), String::from("tan"));
+        assert_eq!(apply(stem1bresolve, "fall"), String::from("fall"));
+        assert_eq!(apply(stem1bresolve, "hiss"), String::from("hiss"));
+        assert_eq!(apply(stem1bresolve, "fail"), String::from("fail"));
+        assert_eq!(apply(stem1bresolve, "fil"), String::from("file"));
     }
 
     #[test]
     fn test_get_char_at_position() {
-        assert_eq!(get_char_at_position(&String::from("fubar"), 3), Some('b'));
-        assert_eq!(get_char_at_position(&String::from("fubar"), 1), Some('f'));
-        assert_eq!(get_char_at_position(&String::from("fubar"), 5), Some('r'));
-        assert_eq!(get_char_at_position(&String::from("fubar"), 6), None);
-        let foo = String::from("fubar");
-        assert_eq!(get_char_at_position(&foo, foo.len()), Some('r'));
+        let foo = Word::from("fubar");
+        assert_eq!(foo.get_char_at_position(3), Some(b'b'));
+        assert_eq!(foo.get_char_at_position(1), Some(b'f'));
+        assert_eq!(foo.get_char_at_position(5), Some(b'r'));
+        assert_eq!(foo.get_char_at_position(6), None);
+        assert_eq!(foo.get_char_at_position(foo.len()), Some(b'r'));
     }
 
     #[test]
     fn test_stem1c() {
-        assert_eq!(stem1c(String::from("happy")), String::from("happi"));
-        assert_eq!(stem1c(String::from("sky")), String::from("sky"));
+        assert_eq!(apply(stem1c, "happy"), String::from("happi"));
+        assert_eq!(apply(stem1c, "sky"), String::from("sky"));
+    }
+
+    // Runs the rules of one of steps 2 to 4 over `word`.
+    #[allow(dead_code)]
+    fn apply_rules(step: &[rules::SuffixRule], word: &str) -> String {
+        let mut my_word = Word::from(word);
+        rules::apply(&mut my_word, step);
+        my_word.as_str().to_string()
     }
 
     #[test]
     fn test_stem2() {
-        assert_eq!(stem2(String::from("relational")), String::from("relate"));
         assert_eq!(
-            stem2(String::from("conditional")),
+            apply_rules(rules::STEP2, "relational"),
+            String::from("relate")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "conditional"),
             String::from("condition")
         );
-        assert_eq!(stem2(String::from("valenci")), String::from("valence"));
-        assert_eq!(stem2(String::from("hesitanci")), String::from("hesitance"));
-        assert_eq!(stem2(String::from("digitizer")), String::from("digitize"));
         assert_eq!(
-            stem2(String::from("conformabli")),
+            apply_rules(rules::STEP2, "valenci"),
+            String::from("valence")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "hesitanci"),
+            String::from("hesitance")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "digitizer"),
+            String::from("digitize")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "conformabli"),
             String::from("conformable")
         );
-        assert_eq!(stem2(String::from("radicalli")), String::from("radical"));
         assert_eq!(
-            stem2(String::from("differentli")),
+            apply_rules(rules::STEP2, "radicalli"),
+            String::from("radical")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "differentli"),
             String::from("different")
         );
         assert_eq!(
-            stem2(String::from("analogousli")),
+            apply_rules(rules::STEP2, "analogousli"),
             String::from("analogous")
         );
-        assert_eq!(stem2(String::from("vileli")), String::from("vile"));
+        assert_eq!(apply_rules(rules::STEP2, "vileli"), String::from("vile"));
         assert_eq!(
-            stem2(String::from("vietnamization")),
+            apply_rules(rules::STEP2, "vietnamization"),
             String::from("vietnamize")
         );
         assert_eq!(
-            stem2(String::from("predication")),
+            apply_rules(rules::STEP2, "predication"),
             String::from("predicate")
         );
-        assert_eq!(stem2(String::from("operator")), String::from("operate"));
-        assert_eq!(stem2(String::from("feudalism")), String::from("feudal"));
         assert_eq!(
-            stem2(String::from("decisiveness")),
+            apply_rules(rules::STEP2, "operator"),
+            String::from("operate")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "feudalism"),
+            String::from("feudal")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "decisiveness"),
             String::from("decisive")
         );
-        assert_eq!(stem2(String::from("hopefulness")), String::from("hopeful"));
-        assert_eq!(stem2(String::from("callousness")), String::from("callous"));
-        assert_eq!(stem2(String::from("formaliti")), String::from("formal"));
         assert_eq!(
-            stem2(String::from("sensitiviti")),
+            apply_rules(rules::STEP2, "hopefulness"),
+            String::from("hopeful")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "callousness"),
+            String::from("callous")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "formaliti"),
+            String::from("formal")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP2, "sensitiviti"),
             String::from("sensitive")
         );
-        assert_eq!(stem2(String::from("sensibiliti")), String::from("sensible"));
+        assert_eq!(
+            apply_rules(rules::STEP2, "sensibiliti"),
+            String::from("sensible")
+        );
     }
 
     #[test]
     fn test_stem3() {
-        assert_eq!(stem3(String::from("electrical")), String::from("electric"));
-        assert_eq!(stem3(String::from("triplicate")), String::from("triplic"));
-        assert_eq!(stem3(String::from("electriciti")), String::from("electric"));
-        assert_eq!(stem3(String::from("hopeful")), String::from("hope"));
-        assert_eq!(stem3(String::from("goodness")), String::from("good"));
-        assert_eq!(stem3(String::from("formative")), String::from("form"));
-        assert_eq!(stem3(String::from("formalize")), String::from("formal"));
+        assert_eq!(
+            apply_rules(rules::STEP3, "electrical"),
+            String::from("electric")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP3, "triplicate"),
+            String::from("triplic")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP3, "electriciti"),
+            String::from("electric")
+        );
+        assert_eq!(apply_rules(rules::STEP3, "hopeful"), String::from("hope"));
+        assert_eq!(apply_rules(rules::STEP3, "goodness"), String::from("good"));
+        assert_eq!(apply_rules(rules::STEP3, "formative"), String::from("form"));
+        assert_eq!(
+            apply_rules(rules::STEP3, "formalize"),
+            String::from("formal")
+        );
     }
 
     #[test]
     fn test_stem4() {
-        assert_eq!(stem4(String::from("revival")), String::from("reviv"));
-        assert_eq!(stem4(String::from("allowance")), String::from("allow"));
-        assert_eq!(stem4(String::from("inference")), String::from("infer"));
-        assert_eq!(stem4(String::from("airliner")), String::from("airlin"));
-        assert_eq!(stem4(String::from("gyroscopic")), String::from("gyroscop"));
-        assert_eq!(stem4(String::from("adjustable")), String::from("adjust"));
-        assert_eq!(stem4(String::from("defensible")), String::from("defens"));
-        assert_eq!(stem4(String::from("irritant")), String::from("irrit"));
-        assert_eq!(stem4(String::from("replacement")), String::from("replac"));
-        assert_eq!(stem4(String::from("adjustment")), String::from("adjust"));
-        assert_eq!(stem4(String::from("dependent")), String::from("depend"));
-        assert_eq!(stem4(String::from("adoption")), String::from("adopt"));
-        assert_eq!(stem4(String::from("homologous")), String::from("homolog"));
-        assert_eq!(stem4(String::from("communism")), String::from("commun"));
-        assert_eq!(stem4(String::from("activate")), String::from("activ"));
-        assert_eq!(stem4(String::from("angulariti")), String::from("angular"));
-        assert_eq!(stem4(String::from("homologous")), String::from("homolog"));
-        assert_eq!(stem4(String::from("effective")), String::from("effect"));
-        assert_eq!(stem4(String::from("bowdlerize")), String::from("bowdler"));
+        assert_eq!(apply_rules(rules::STEP4, "revival"), String::from("reviv"));
+        assert_eq!(
+            apply_rules(rules::STEP4, "allowance"),
+            String::from("allow")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "inference"),
+            String::from("infer")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "airliner"),
+            String::from("airlin")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "gyroscopic"),
+            String::from("gyroscop")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "adjustable"),
+            String::from("adjust")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "defensible"),
+            String::from("defens")
+        );
+        assert_eq!(apply_rules(rules::STEP4, "irritant"), String::from("irrit"));
+        assert_eq!(
+            apply_rules(rules::STEP4, "replacement"),
+            String::from("replac")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "adjustment"),
+            String::from("adjust")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "dependent"),
+            String::from("depend")
+        );
+        assert_eq!(apply_rules(rules::STEP4, "adoption"), String::from("adopt"));
+        assert_eq!(
+            apply_rules(rules::STEP4, "homologous"),
+            String::from("homolog")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "communism"),
+            String::from("commun")
+        );
+        assert_eq!(apply_rules(rules::STEP4, "activate"), String::from("activ"));
+        assert_eq!(
+            apply_rules(rules::STEP4, "angulariti"),
+            String::from("angular")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "homologous"),
+            String::from("homolog")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "effective"),
+            String::from("effect")
+        );
+        assert_eq!(
+            apply_rules(rules::STEP4, "bowdlerize"),
+            String::from("bowdler")
+        );
     }
 
     #[test]
     fn test_stem5a() {
-        assert_eq!(stem5a(String::from("probate")), String::from("probat"));
-        assert_eq!(stem5a(String::from("rate")), String::from("rate"));
-        assert_eq!(stem5a(String::from("cease")), String::from("ceas"));
-        assert_eq!(stem5a(String::from("trouble")), String::from("troubl"));
-        assert_eq!(stem5a(String::from("trocawe")), String::from("trocaw"));
-        assert_eq!(stem5a(String::from("pcace")), String::from("pcace"));
+        assert_eq!(apply(stem5a, "probate"), String::from("probat"));
+        assert_eq!(apply(stem5a, "rate"), String::from("rate"));
+        assert_eq!(apply(stem5a, "cease"), String::from("ceas"));
+        assert_eq!(apply(stem5a, "trouble"), String::from("troubl"));
+        assert_eq!(apply(stem5a, "trocawe"), String::from("trocaw"));
+        assert_eq!(apply(stem5a, "pcace"), String::from("pcace"));
     }
 
     #[test]
     fn test_stem5b() {
-        assert_eq!(stem5b(String::from("controll")), String::from("control"));
-        assert_eq!(stem5b(String::from("roll")), String::from("roll"));
+        assert_eq!(apply(stem5b, "controll"), String::from("control"));
+        assert_eq!(apply(stem5b, "roll"), String::from("roll"));
     }
 }
@@ -0,0 +1,566 @@
+// The Lovins stemmer, from J. B. Lovins, "Development of a Stemming
+// Algorithm", Mechanical Translation and Computational Linguistics 11 (1968).
+//
+// The stemmer removes the longest of 294 endings whose condition holds for
+// the stem left in front of it, then undoubles a final consonant and applies
+// the one of Lovins's 34 transformation rules with the longest ending that
+// matches the stem. Every condition requires a stem of at least two letters.
+// Where the paper leaves the order of the transformations open, this follows
+// the Snowball definition at https://snowballstem.org/algorithms/lovins/stemmer.html.
+
+use std::borrow::Cow;
+
+use crate::{borrow_prefix, validate, Stem, StemError};
+
+// The conditions on the stem that an ending can be removed from, named by
+// their letters in the paper.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+    // No restriction.
+    A,
+    // The stem has at least 3 letters.
+    B,
+    // At least 4 letters.
+    C,
+    // At least 5 letters.
+    D,
+    // It does not end with e.
+    E,
+    // At least 3 letters, not ending with e.
+    F,
+    // At least 3 letters, ending with f.
+    G,
+    // It ends with t or ll.
+    H,
+    // It does not end with o or e.
+    I,
+    // It does not end with a or e.
+    J,
+    // At least 3 letters, ending with l, i or u*e.
+    K,
+    // It does not end with u, x or s, unless the s follows an o.
+    L,
+    // It does not end with a, c, e or m.
+    M,
+    // At least 4 letters after s**, at least 3 elsewhere.
+    N,
+    // It ends with l or i.
+    O,
+    // It does not end with c.
+    P,
+    // At least 3 letters, not ending with l or n.
+    Q,
+    // It ends with n or r.
+    R,
+    // It ends with dr, or with t but not tt.
+    S,
+    // It ends with s, or with t but not ot.
+    T,
+    // It ends with l, m, n or r.
+    U,
+    // It ends with c.
+    V,
+    // It does not end with s or u.
+    W,
+    // It ends with l, i or u*e.
+    X,
+    // It ends with in.
+    Y,
+    // It does not end with f.
+    Z,
+    // It ends with d, f, ph, th, l, er, or, es or t.
+    AA,
+    // At least 3 letters, not ending with met or ryst.
+    BB,
+    // It ends with l.
+    CC,
+}
+
+use self::Condition::*;
+
+impl Condition {
+    fn holds(self, stem: &[u8]) -> bool {
+        let len = stem.len();
+        let ends = |suffix: &str| stem.ends_with(suffix.as_bytes());
+        let any = |suffixes: &[&str]| suffixes.iter().any(|suffix| ends(suffix));
+        // u*e: u, any letter, e.
+        let u_e = len >= 3 && stem[len - 3] == b'u' && ends("e");
+        len >= 2
+            && match self {
+                A => true,
+                B => len >= 3,
+                C => len >= 4,
+                D => len >= 5,
+                E => !ends("e"),
+                F => len >= 3 && !ends("e"),
+                G => len >= 3 && ends("f"),
+                H => any(&["t", "ll"]),
+                I => !any(&["o", "e"]),
+                J => !any(&["a", "e"]),
+                K => len >= 3 && (any(&["l", "i"]) || u_e),
+                L => !any(&["u", "x"]) && (!ends("s") || ends("os")),
+                M => !any(&["a", "c", "e", "m"]),
+                N => len >= 3 && (stem[len - 3] != b's' || len >= 4),
+                O => any(&["l", "i"]),
+                P => !ends("c"),
+                Q => len >= 3 && !any(&["l", "n"]),
+                R => any(&["n", "r"]),
+                S => ends("dr") || (ends("t") && !ends("tt")),
+                T => ends("s") || (ends("t") && !ends("ot")),
+                U => any(&["l", "m", "n", "r"]),
+                V => ends("c"),
+                W => !any(&["s", "u"]),
+                X => any(&["l", "i"]) || u_e,
+                Y => ends("in"),
+                Z => !ends("f"),
+                AA => any(&["d", "f", "ph", "th", "l", "er", "or", "es", "t"]),
+                BB => len >= 3 && !any(&["met", "ryst"]),
+                CC => ends("l"),
+            }
+    }
+}
+
+// The endings, longest first, each with the condition for removing it.
+const ENDINGS: &[(&str, Condition)] = &[
+    ("alistically", B),
+    ("arizability", A),
+    ("izationally", B),
+    ("antialness", A),
+    ("arisations", A),
+    ("arizations", A),
+    ("entialness", A),
+    ("allically", C),
+    ("antaneous", A),
+    ("antiality", A),
+    ("arisation", A),
+    ("arization", A),
+    ("ationally", B),
+    ("ativeness", A),
+    ("eableness", E),
+    ("entations", A),
+    ("entiality", A),
+    ("entialize", A),
+    ("entiation", A),
+    ("ionalness", A),
+    ("istically", A),
+    ("itousness", A),
+    ("izability", A),
+    ("izational", A),
+    ("ableness", A),
+    ("arizable", A),
+    ("entation", A),
+    ("entially", A),
+    ("eousness", A),
+    ("ibleness", A),
+    ("icalness", A),
+    ("ionalism", A),
+    ("ionality", A),
+    ("ionalize", A),
+    ("iousness", A),
+    ("izations", A),
+    ("lessness", A),
+    ("ability", A),
+    ("aically", A),
+    ("alistic", B),
+    ("alities", A),
+    ("ariness", E),
+    ("aristic", A),
+    ("arizing", A),
+    ("ateness", A),
+    ("atingly", A),
+    ("ational", B),
+    ("atively", A),
+    ("ativism", A),
+    ("elihood", E),
+    ("encible", A),
+    ("entally", A),
+    ("entials", A),
+    ("entiate", A),
+    ("entness", A),
+    ("fulness", A),
+    ("ibility", A),
+    ("icalism", A),
+    ("icalist", A),
+    ("icality", A),
+    ("icalize", A),
+    ("ication", G),
+    ("icianry", A),
+    ("ination", A),
+    ("ingness", A),
+    ("ionally", A),
+    ("isation", A),
+    ("ishness", A),
+    ("istical", A),
+    ("iteness", A),
+    ("iveness", A),
+    ("ivistic", A),
+    ("ivities", A),
+    ("ization", F),
+    ("izement", A),
+    ("oidally", A),
+    ("ousness", A),
+    ("aceous", A),
+    ("acious", B),
+    ("action", G),
+    ("alness", A),
+    ("ancial", A),
+    ("ancies", A),
+    ("ancing", B),
+    ("ariser", A),
+    ("arized", A),
+    ("arizer", A),
+    ("atable", A),
+    ("ations", B),
+    ("atives", A),
+    ("eature", Z),
+    ("efully", A),
+    ("encies", A),
+    ("encing", A),
+    ("ential", A),
+    ("enting", C),
+    ("entist", A),
+    ("eously", A),
+    ("ialist", A),
+    ("iality", A),
+    ("ialize", A),
+    ("ically", A),
+    ("icance", A),
+    ("icians", A),
+    ("icists", A),
+    ("ifully", A),
+    ("ionals", A),
+    ("ionate", D),
+    ("ioning", A),
+    ("ionist", A),
+    ("iously", A),
+    ("istics", A),
+    ("izable", E),
+    ("lessly", A),
+    ("nesses", A),
+    ("oidism", A),
+    ("acies", A),
+    ("acity", A),
+    ("aging", B),
+    ("aical", A),
+    ("alist", A),
+    ("alism", B),
+    ("ality", A),
+    ("alize", A),
+    ("allic", BB),
+    ("anced", B),
+    ("ances", B),
+    ("antic", C),
+    ("arial", A),
+    ("aries", A),
+    ("arily", A),
+    ("arity", B),
+    ("arize", A),
+    ("aroid", A),
+    ("ately", A),
+    ("ating", I),
+    ("ation", B),
+    ("ative", A),
+    ("ators", A),
+    ("atory", A),
+    ("ature", E),
+    ("early", Y),
+    ("ehood", A),
+    ("eless", A),
+    ("elity", A),
+    ("ement", A),
+    ("enced", A),
+    ("ences", A),
+    ("eness", E),
+    ("ening", E),
+    ("ental", A),
+    ("ented", C),
+    ("ently", A),
+    ("fully", A),
+    ("ially", A),
+    ("icant", A),
+    ("ician", A),
+    ("icide", A),
+    ("icism", A),
+    ("icist", A),
+    ("icity", A),
+    ("idine", I),
+    ("iedly", A),
+    ("ihood", A),
+    ("inate", A),
+    ("iness", A),
+    ("ingly", B),
+    ("inism", J),
+    ("inity", CC),
+    ("ional", A),
+    ("ioned", A),
+    ("ished", A),
+    ("istic", A),
+    ("ities", A),
+    ("itous", A),
+    ("ively", A),
+    ("ivity", A),
+    ("izers", F),
+    ("izing", F),
+    ("oidal", A),
+    ("oides", A),
+    ("otide", A),
+    ("ously", A),
+    ("able", A),
+    ("ably", A),
+    ("ages", B),
+    ("ally", B),
+    ("ance", B),
+    ("ancy", B),
+    ("ants", B),
+    ("aric", A),
+    ("arly", K),
+    ("ated", I),
+    ("ates", A),
+    ("atic", B),
+    ("ator", A),
+    ("ealy", Y),
+    ("edly", E),
+    ("eful", A),
+    ("eity", A),
+    ("ence", A),
+    ("ency", A),
+    ("ened", E),
+    ("enly", E),
+    ("eous", A),
+    ("hood", A),
+    ("ials", A),
+    ("ians", A),
+    ("ible", A),
+    ("ibly", A),
+    ("ical", A),
+    ("ides", L),
+    ("iers", A),
+    ("iful", A),
+    ("ines", M),
+    ("ings", N),
+    ("ions", B),
+    ("ious", A),
+    ("isms", B),
+    ("ists", A),
+    ("itic", H),
+    ("ized", F),
+    ("izer", F),
+    ("less", A),
+    ("lily", A),
+    ("ness", A),
+    ("ogen", A),
+    ("ward", A),
+    ("wise", A),
+    ("ying", B),
+    ("yish", A),
+    ("acy", A),
+    ("age", B),
+    ("aic", A),
+    ("als", BB),
+    ("ant", B),
+    ("ars", O),
+    ("ary", F),
+    ("ata", A),
+    ("ate", A),
+    ("eal", Y),
+    ("ear", Y),
+    ("ely", E),
+    ("ene", E),
+    ("ent", C),
+    ("ery", E),
+    ("ese", A),
+    ("ful", A),
+    ("ial", A),
+    ("ian", A),
+    ("ics", A),
+    ("ide", L),
+    ("ied", A),
+    ("ier", A),
+    ("ies", P),
+    ("ily", A),
+    ("ine", M),
+    ("ing", N),
+    ("ion", Q),
+    ("ish", C),
+    ("ism", B),
+    ("ist", A),
+    ("ite", AA),
+    ("ity", A),
+    ("ium", A),
+    ("ive", A),
+    ("ize", F),
+    ("oid", A),
+    ("one", R),
+    ("ous", A),
+    ("ae", A),
+    ("al", BB),
+    ("ar", X),
+    ("as", B),
+    ("ed", E),
+    ("en", F),
+    ("es", E),
+    ("ia", A),
+    ("ic", A),
+    ("is", A),
+    ("ly", B),
+    ("on", S),
+    ("or", T),
+    ("um", U),
+    ("us", V),
+    ("yl", R),
+    ("'s", A),
+    ("s'", A),
+    ("a", A),
+    ("e", A),
+    ("i", A),
+    ("o", A),
+    ("s", W),
+    ("y", B),
+];
+
+// The final consonants that are undoubled.
+const DOUBLES: &[u8] = b"bdglmnprst";
+
+// The transformation rules: the ending of the stem, the letters it is
+// replaced with, and the letters it must not follow.
+const TRANSFORMATIONS: &[(&str, &str, &str)] = &[
+    ("iev", "ief", ""),
+    ("uct", "uc", ""),
+    ("umpt", "um", ""),
+    ("rpt", "rb", ""),
+    ("urs", "ur", ""),
+    ("istr", "ister", ""),
+    ("metr", "meter", ""),
+    ("olv", "olut", ""),
+    ("ul", "l", "aio"),
+    ("bex", "bic", ""),
+    ("dex", "dic", ""),
+    ("pex", "pic", ""),
+    ("tex", "tic", ""),
+    ("ax", "ac", ""),
+    ("ex", "ec", ""),
+    ("ix", "ic", ""),
+    ("lux", "luc", ""),
+    ("uad", "uas", ""),
+    ("vad", "vas", ""),
+    ("cid", "cis", ""),
+    ("lid", "lis", ""),
+    ("erid", "eris", ""),
+    ("pand", "pans", ""),
+    ("end", "ens", "s"),
+    ("ond", "ons", ""),
+    ("lud", "lus", ""),
+    ("rud", "rus", ""),
+    ("her", "hes", "pt"),
+    ("mit", "mis", ""),
+    ("ent", "ens", "m"),
+    ("ert", "ers", ""),
+    ("et", "es", "n"),
+    ("yt", "ys", ""),
+    ("yz", "ys", ""),
+];
+
+/// The Lovins algorithm, as run by `stem`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Lovins;
+
+impl Stem for Lovins {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        Ok(borrow_prefix(word.trim(), &stem(word)?))
+    }
+}
+
+/// Stems `word` with the Lovins algorithm.
+///
+/// The input is validated, trimmed and lowercased the same way as for the
+/// Porter `stem`.
+pub fn stem(word: &str) -> Result<String, StemError> {
+    validate(word)?;
+    let mut word = word.trim().to_ascii_lowercase().into_bytes();
+    remove_ending(&mut word);
+    undouble(&mut word);
+    transform(&mut word);
+    Ok(word.into_iter().map(char::from).collect())
+}
+
+fn remove_ending(word: &mut Vec<u8>) {
+    let ending = ENDINGS.iter().find(|(ending, condition)| {
+        word.len() > ending.len()
+            && word.ends_with(ending.as_bytes())
+            && condition.holds(&word[..word.len() - ending.len()])
+    });
+    if let Some((ending, _)) = ending {
+        word.truncate(word.len() - ending.len());
+    }
+}
+
+fn undouble(word: &mut Vec<u8>) {
+    if let [.., a, b] = word[..] {
+        if a == b && DOUBLES.contains(&a) {
+            word.pop();
+        }
+    }
+}
+
+// Of the rules whose ending matches, the one with the longest ending applies,
+// provided the letter in front of it is not excluded.
+fn transform(word: &mut Vec<u8>) {
+    let rule = TRANSFORMATIONS
+        .iter()
+        .filter(|(ending, _, _)| word.ends_with(ending.as_bytes()))
+        .max_by_key(|(ending, _, _)| ending.len());
+    if let Some((ending, replacement, except)) = rule {
+        let start = word.len() - ending.len();
+        let before = start.checked_sub(1).map(|i| word[i]);
+        if before.is_some_and(|letter| except.as_bytes().contains(&letter)) {
+            return;
+        }
+        word.truncate(start);
+        word.extend_from_slice(replacement.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(ENDINGS.len(), 294);
+        assert_eq!(TRANSFORMATIONS.len(), 34);
+        assert!(ENDINGS.windows(2).all(|w| w[0].0.len() >= w[1].0.len()));
+    }
+
+    #[test]
+    fn test_conditions() {
+        assert!(!A.holds(b"n"));
+        assert!(N.holds(b"bit") && !N.holds(b"bi"));
+        assert!(!N.holds(b"sun") && N.holds(b"asun"));
+        assert!(K.holds(b"fuse") && K.holds(b"lil") && !K.holds(b"fise"));
+        assert!(L.holds(b"cos") && !L.holds(b"cas"));
+        assert!(S.holds(b"hydr") && S.holds(b"bat") && !S.holds(b"batt"));
+    }
+
+    #[test]
+    fn test_stem() -> Result<(), StemError> {
+        // The examples of the paper: each pair is conflated.
+        assert_eq!(stem("matrix")?, "matric");
+        assert_eq!(stem("matrices")?, "matric");
+        assert_eq!(stem("absorption")?, "absorb");
+        assert_eq!(stem("absorbing")?, "absorb");
+        assert_eq!(stem("reduction")?, "reduc");
+        assert_eq!(stem("reduce")?, "reduc");
+        assert_eq!(stem("dissolution")?, "dissolut");
+        assert_eq!(stem("dissolve")?, "dissolut");
+        assert_eq!(stem("sitting")?, "sit");
+
+        assert!(matches!(Lovins.stem("sitting")?, Cow::Borrowed("sit")));
+        assert!(matches!(Lovins.stem("dissolve")?, Cow::Owned(_)));
+        assert_eq!(stem("  Nationally ")?, "nat");
+        assert_eq!(stem("is")?, "is");
+        assert!(stem("").is_err());
+        assert!(stem("na\u{ef}ve").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,412 @@
+// The Porter2 stemmer, published as the Snowball "English" stemmer. The steps
+// follow the definition at https://snowballstem.org/algorithms/english/stemmer.html
+// and, like it, work on a lowercase word where Y marks a y that is treated as
+// a consonant.
+
+use std::borrow::Cow;
+
+use crate::{borrow_prefix, validate, Stem, StemError};
+
+// Words that are stemmed by lookup rather than by the rules.
+const EXCEPTIONS: &[(&str, &str)] = &[
+    ("skis", "ski"),
+    ("skies", "sky"),
+    ("dying", "die"),
+    ("lying", "lie"),
+    ("tying", "tie"),
+    ("idly", "idl"),
+    ("gently", "gentl"),
+    ("ugly", "ugli"),
+    ("early", "earli"),
+    ("only", "onli"),
+    ("singly", "singl"),
+    ("sky", "sky"),
+    ("news", "news"),
+    ("howe", "howe"),
+    ("atlas", "atlas"),
+    ("cosmos", "cosmos"),
+    ("bias", "bias"),
+    ("andes", "andes"),
+];
+
+// Words left alone once the plural has been removed in step 1a.
+const INVARIANTS: &[&str] = &[
+    "inning", "outing", "canning", "herring", "earring", "proceed", "exceed", "succeed",
+];
+
+// Words whose R1 starts after these prefixes rather than at the usual place.
+const R1_PREFIXES: &[&str] = &["gener", "commun", "arsen"];
+
+const STEP2: &[(&str, &str)] = &[
+    ("tional", "tion"),
+    ("enci", "ence"),
+    ("anci", "ance"),
+    ("abli", "able"),
+    ("entli", "ent"),
+    ("izer", "ize"),
+    ("ization", "ize"),
+    ("ational", "ate"),
+    ("ation", "ate"),
+    ("ator", "ate"),
+    ("alism", "al"),
+    ("aliti", "al"),
+    ("alli", "al"),
+    ("fulness", "ful"),
+    ("ousli", "ous"),
+    ("ousness", "ous"),
+    ("iveness", "ive"),
+    ("iviti", "ive"),
+    ("biliti", "ble"),
+    ("bli", "ble"),
+    ("ogi", "og"),
+    ("fulli", "ful"),
+    ("lessli", "less"),
+    ("li", ""),
+];
+
+const STEP3: &[(&str, &str)] = &[
+    ("tional", "tion"),
+    ("ational", "ate"),
+    ("alize", "al"),
+    ("icate", "ic"),
+    ("iciti", "ic"),
+    ("ical", "ic"),
+    ("ful", ""),
+    ("ness", ""),
+    ("ative", ""),
+];
+
+const STEP4: &[&str] = &[
+    "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ism", "ate",
+    "iti", "ous", "ive", "ize", "ion",
+];
+
+/// The Porter2 algorithm, as run by `stem`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Porter2;
+
+impl Stem for Porter2 {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        Ok(borrow_prefix(word.trim(), &stem(word)?))
+    }
+}
+
+/// Stems `word` with the Porter2 algorithm.
+///
+/// The input is validated, trimmed and lowercased the same way as for the
+/// original Porter `stem`.
+pub fn stem(word: &str) -> Result<String, StemError> {
+    validate(word)?;
+    let word = word.trim().to_ascii_lowercase();
+    if let Some(&(_, stem)) = EXCEPTIONS.iter().find(|(from, _)| *from == word) {
+        return Ok(stem.to_string());
+    }
+    if word.len() <= 2 {
+        return Ok(word);
+    }
+    let mut word = Word::new(word.into_bytes());
+    word.step1a();
+    if !INVARIANTS
+        .iter()
+        .any(|w| w.as_bytes() == word.bytes.as_slice())
+    {
+        word.step1b();
+        word.step1c();
+        word.step2();
+        word.step3();
+        word.step4();
+        word.step5();
+    }
+    Ok(word.finish())
+}
+
+fn is_vowel(letter: u8) -> bool {
+    matches!(letter, b'a' | b'e' | b'i' | b'o' | b'u' | b'y')
+}
+
+fn is_double(letter: u8) -> bool {
+    matches!(
+        letter,
+        b'b' | b'd' | b'f' | b'g' | b'm' | b'n' | b'p' | b'r' | b't'
+    )
+}
+
+fn is_li_ending(letter: u8) -> bool {
+    matches!(
+        letter,
+        b'c' | b'd' | b'e' | b'g' | b'h' | b'k' | b'm' | b'n' | b'r' | b't'
+    )
+}
+
+// The position after the first non-vowel following a vowel, searching from
+// `start`, or the end of the word if there is none.
+fn region_after(bytes: &[u8], start: usize) -> usize {
+    let mut seen_vowel = false;
+    for (position, &letter) in bytes.iter().enumerate().skip(start) {
+        if is_vowel(letter) {
+            seen_vowel = true;
+        } else if seen_vowel {
+            return position + 1;
+        }
+    }
+    bytes.len()
+}
+
+struct Word {
+    bytes: Vec<u8>,
+    r1: usize,
+    r2: usize,
+}
+
+impl Word {
+    fn new(mut bytes: Vec<u8>) -> Word {
+        if bytes.first() == Some(&b'\'') {
+            bytes.remove(0);
+        }
+        for position in 0..bytes.len() {
+            if bytes[position] == b'y' && (position == 0 || is_vowel(bytes[position - 1])) {
+                bytes[position] = b'Y';
+            }
+        }
+        let r1 = R1_PREFIXES
+            .iter()
+            .find(|prefix| bytes.starts_with(prefix.as_bytes()))
+            .map(|prefix| prefix.len())
+            .unwrap_or_else(|| region_after(&bytes, 0));
+        let r2 = region_after(&bytes, r1);
+        Word { bytes, r1, r2 }
+    }
+
+    fn finish(mut self) -> String {
+        for letter in self.bytes.iter_mut() {
+            if *letter == b'Y' {
+                *letter = b'y';
+            }
+        }
+        self.bytes.into_iter().map(char::from).collect()
+    }
+
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn ends_with(&self, suffix: &str) -> bool {
+        self.bytes.ends_with(suffix.as_bytes())
+    }
+
+    fn replace_suffix(&mut self, suffix_len: usize, replacement: &str) {
+        self.bytes.truncate(self.len() - suffix_len);
+        self.bytes.extend_from_slice(replacement.as_bytes());
+    }
+
+    // The longest of `suffixes` that the word ends with.
+    fn longest_suffix<'a, T>(
+        &self,
+        suffixes: &'a [T],
+        suffix: impl Fn(&T) -> &str,
+    ) -> Option<&'a T> {
+        suffixes
+            .iter()
+            .filter(|entry| self.ends_with(suffix(entry)))
+            .max_by_key(|entry| suffix(entry).len())
+    }
+
+    fn in_r1(&self, suffix_len: usize) -> bool {
+        self.len() - suffix_len >= self.r1
+    }
+
+    fn in_r2(&self, suffix_len: usize) -> bool {
+        self.len() - suffix_len >= self.r2
+    }
+
+    fn has_vowel_before(&self, end: usize) -> bool {
+        self.bytes[..end].iter().any(|&letter| is_vowel(letter))
+    }
+
+    // A short syllable is a vowel followed by a non-vowel other than w, x or
+    // Y and preceded by a non-vowel, or a vowel at the beginning of the word
+    // followed by a non-vowel.
+    fn ends_with_short_syllable(&self, end: usize) -> bool {
+        let bytes = &self.bytes[..end];
+        match *bytes {
+            [first, second] => is_vowel(first) && !is_vowel(second),
+            [.., first, second, third] => {
+                !is_vowel(first)
+                    && is_vowel(second)
+                    && !is_vowel(third)
+                    && !matches!(third, b'w' | b'x' | b'Y')
+            }
+            _ => false,
+        }
+    }
+
+    fn is_short(&self) -> bool {
+        self.r1 >= self.len() && self.ends_with_short_syllable(self.len())
+    }
+
+    fn step1a(&mut self) {
+        for suffix in &["'s'", "'s", "'"] {
+            if self.ends_with(suffix) {
+                self.replace_suffix(suffix.len(), "");
+                break;
+            }
+        }
+        if self.ends_with("sses") {
+            self.replace_suffix(4, "ss");
+        } else if self.ends_with("ied") || self.ends_with("ies") {
+            let replacement = if self.len() > 4 { "i" } else { "ie" };
+            self.replace_suffix(3, replacement);
+        } else if self.ends_with("us") || self.ends_with("ss") {
+        } else if self.ends_with("s") && self.len() >= 2 && self.has_vowel_before(self.len() - 2) {
+            self.replace_suffix(1, "");
+        }
+    }
+
+    fn step1b(&mut self) {
+        let suffixes = ["eed", "eedly", "ed", "edly", "ing", "ingly"];
+        let suffix = match self.longest_suffix(&suffixes, |s| s) {
+            Some(suffix) => *suffix,
+            None => return,
+        };
+        if suffix.starts_with("eed") {
+            if self.in_r1(suffix.len()) {
+                self.replace_suffix(suffix.len(), "ee");
+            }
+            return;
+        }
+        if !self.has_vowel_before(self.len() - suffix.len()) {
+            return;
+        }
+        self.replace_suffix(suffix.len(), "");
+        if self.ends_with("at") || self.ends_with("bl") || self.ends_with("iz") {
+            self.bytes.push(b'e');
+        } else if let [.., a, b] = *self.bytes {
+            if a == b && is_double(b) {
+                self.bytes.pop();
+            } else if self.is_short() {
+                self.bytes.push(b'e');
+            }
+        }
+    }
+
+    fn step1c(&mut self) {
+        let len = self.len();
+        if len > 2 && (self.ends_with("y") || self.ends_with("Y")) && !is_vowel(self.bytes[len - 2])
+        {
+            self.bytes[len - 1] = b'i';
+        }
+    }
+
+    fn step2(&mut self) {
+        if let Some(&(suffix, replacement)) = self.longest_suffix(STEP2, |(s, _)| s) {
+            if !self.in_r1(suffix.len()) {
+                return;
+            }
+            let preceding = self
+                .len()
+                .checked_sub(suffix.len() + 1)
+                .map(|i| self.bytes[i]);
+            match suffix {
+                "ogi" if preceding != Some(b'l') => {}
+                "li" if !preceding.is_some_and(is_li_ending) => {}
+                _ => self.replace_suffix(suffix.len(), replacement),
+            }
+        }
+    }
+
+    fn step3(&mut self) {
+        if let Some(&(suffix, replacement)) = self.longest_suffix(STEP3, |(s, _)| s) {
+            if !self.in_r1(suffix.len()) || (suffix == "ative" && !self.in_r2(suffix.len())) {
+                return;
+            }
+            self.replace_suffix(suffix.len(), replacement);
+        }
+    }
+
+    fn step4(&mut self) {
+        if let Some(&suffix) = self.longest_suffix(STEP4, |s| s) {
+            if !self.in_r2(suffix.len()) {
+                return;
+            }
+            if suffix == "ion" {
+                let preceding = self.len().checked_sub(4).map(|i| self.bytes[i]);
+                if !matches!(preceding, Some(b's') | Some(b't')) {
+                    return;
+                }
+            }
+            self.replace_suffix(suffix.len(), "");
+        }
+    }
+
+    fn step5(&mut self) {
+        let len = self.len();
+        if self.ends_with("e") {
+            if self.in_r2(1) || (self.in_r1(1) && !self.ends_with_short_syllable(len - 1)) {
+                self.bytes.pop();
+            }
+        } else if self.ends_with("ll") && self.in_r2(1) {
+            self.bytes.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regions() {
+        let word = Word::new(b"beautiful".to_vec());
+        assert_eq!((word.r1, word.r2), (5, 7));
+        let word = Word::new(b"beauty".to_vec());
+        assert_eq!((word.r1, word.r2), (5, 6));
+        let word = Word::new(b"beau".to_vec());
+        assert_eq!((word.r1, word.r2), (4, 4));
+        let word = Word::new(b"animadversion".to_vec());
+        assert_eq!((word.r1, word.r2), (2, 4));
+        let word = Word::new(b"sprinkled".to_vec());
+        assert_eq!((word.r1, word.r2), (5, 9));
+        let word = Word::new(b"eucharist".to_vec());
+        assert_eq!((word.r1, word.r2), (3, 6));
+        let word = Word::new(b"generous".to_vec());
+        assert_eq!(word.r1, 5);
+    }
+
+    #[test]
+    fn test_y_marking() {
+        assert_eq!(Word::new(b"youth".to_vec()).bytes, b"Youth");
+        assert_eq!(Word::new(b"boyish".to_vec()).bytes, b"boYish");
+        assert_eq!(Word::new(b"sayyid".to_vec()).bytes, b"saYyid");
+        assert_eq!(Word::new(b"'tis".to_vec()).bytes, b"tis");
+    }
+
+    #[test]
+    fn test_stem() -> Result<(), StemError> {
+        assert!(matches!(Porter2.stem(" gaps")?, Cow::Borrowed("gap")));
+        assert!(matches!(Porter2.stem("skies")?, Cow::Owned(_)));
+        assert_eq!(stem("skies")?, "sky");
+        assert_eq!(stem("news")?, "news");
+        assert_eq!(stem("gently")?, "gentl");
+        assert_eq!(stem("ties")?, "tie");
+        assert_eq!(stem("cries")?, "cri");
+        assert_eq!(stem("gas")?, "gas");
+        assert_eq!(stem("gaps")?, "gap");
+        assert_eq!(stem("kiwis")?, "kiwi");
+        assert_eq!(stem("hopping")?, "hop");
+        assert_eq!(stem("hoped")?, "hope");
+        assert_eq!(stem("cry")?, "cri");
+        assert_eq!(stem("by")?, "by");
+        assert_eq!(stem("say")?, "say");
+        assert_eq!(stem("fluently")?, "fluentli");
+        assert_eq!(stem("analogi")?, "analog");
+        assert_eq!(stem("knightly")?, "knight");
+        assert_eq!(stem("generously")?, "generous");
+        assert_eq!(stem("communism")?, "communism");
+        assert_eq!(stem("succeeding")?, "succeed");
+        assert_eq!(stem("exceed")?, "exceed");
+        assert_eq!(stem("dog's")?, "dog");
+        assert_eq!(stem("Consolingly")?, "consol");
+        assert!(stem("na\u{ef}ve").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,461 @@
+// Stemmers defined by a rule file rather than in code.
+//
+// A rule file is a list of steps, each a list of suffix rules written in the
+// notation of Porter's paper:
+//
+//     # Plurals, then medical suffixes.
+//     step plural
+//         sses -> ss
+//         ies -> i
+//         ss -> ss
+//         is -> is
+//         (m>0) s ->
+//     step medical
+//         (m>0) ectomy ->
+//         (m>0 and not *o) itis ->
+//         (m>1 and (*S or *T)) ion ->
+//
+// `step NAME` starts a step. A rule is an optional condition in parentheses,
+// a suffix, `->` and a replacement, which may be empty. Everything after a
+// `#` is a comment. Suffixes and replacements are lowercase letters.
+//
+// A condition combines `m>N` and `m=N` (the measure of the stem), `*v*` (the
+// stem has a vowel), `*d` (it ends with a double consonant), `*o` (it ends
+// consonant-vowel-consonant, the last not W, X or Y) and `*S` (it ends with S,
+// or any of the capital letters given, as in `*ST`) with `and`, `or`, `not`
+// and parentheses.
+//
+// The steps run in order, and each applies at most one rule, as the steps of
+// `Stemmer` do: the first rule whose suffix matches and whose condition holds.
+// When the condition is `m>N`, or `m>N and` something else, a word that has
+// the suffix but fails the measure test ends the step instead.
+
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::rules::{self, Condition, SuffixRule};
+use crate::{validate, Stem, StemError, Word};
+
+/// An error in a rule file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, message: String) -> ParseError {
+        ParseError { line, message }
+    }
+
+    /// The line of the error, counting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// A named step of a `RuleStemmer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub name: String,
+    pub rules: Vec<SuffixRule>,
+}
+
+/// A stemmer that runs the steps read from a rule file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleStemmer {
+    steps: Vec<Step>,
+}
+
+impl RuleStemmer {
+    /// Reads the steps of a stemmer from the text of a rule file.
+    pub fn parse(source: &str) -> Result<RuleStemmer, ParseError> {
+        let mut steps: Vec<Step> = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let error = |message: String| ParseError {
+                line: index + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            if !line.contains("->") && words.next() == Some("step") {
+                let name = words
+                    .next()
+                    .ok_or_else(|| error("step without a name".to_string()))?;
+                if let Some(extra) = words.next() {
+                    return Err(error(format!("unexpected {:?} after step name", extra)));
+                }
+                steps.push(Step {
+                    name: name.to_string(),
+                    rules: Vec::new(),
+                });
+                continue;
+            }
+            let step = steps
+                .last_mut()
+                .ok_or_else(|| error("rule before the first step".to_string()))?;
+            step.rules.push(parse_rule(line).map_err(error)?);
+        }
+        Ok(RuleStemmer { steps })
+    }
+
+    /// Reads the steps of a stemmer from the rule file at `path`. Errors in
+    /// the file are returned as `InvalidData` errors.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<RuleStemmer> {
+        RuleStemmer::parse(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Stems `word` with the steps. The input is validated, trimmed and
+    /// lowercased the same way as for the Porter `stem`.
+    pub fn stem(&self, word: &str) -> Result<String, StemError> {
+        validate(word)?;
+        let mut my_word = Word::from(word.trim());
+        for step in &self.steps {
+            rules::apply(&mut my_word, &step.rules);
+        }
+        Ok(my_word.as_str().to_string())
+    }
+}
+
+impl Stem for RuleStemmer {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        RuleStemmer::stem(self, word).map(Cow::Owned)
+    }
+}
+
+impl FromStr for RuleStemmer {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<RuleStemmer, ParseError> {
+        RuleStemmer::parse(source)
+    }
+}
+
+fn parse_rule(line: &str) -> Result<SuffixRule, String> {
+    let (condition, rest) = match line.strip_prefix('(') {
+        Some(after) => {
+            let close = closing_parenthesis(after).ok_or("unbalanced parentheses")?;
+            (Some(parse_condition(&after[..close])?), &after[close + 1..])
+        }
+        None => (None, line),
+    };
+    let (suffix, replacement) = rest
+        .split_once("->")
+        .ok_or("expected a rule of the form (condition) suffix -> replacement")?;
+    let (suffix, replacement) = (suffix.trim(), replacement.trim());
+    if suffix.is_empty() {
+        return Err("rule without a suffix".to_string());
+    }
+    for text in &[suffix, replacement] {
+        if !text.bytes().all(|b| b.is_ascii_lowercase()) {
+            return Err(format!("{:?} is not all lowercase letters", text));
+        }
+    }
+    // A top-level m>N becomes the rule's measure test, which ends the step
+    // when it fails.
+    let (min_measure, condition) = match condition {
+        Some(Condition::MeasureAbove(min)) => (Some(min), None),
+        Some(Condition::All(mut conditions)) => {
+            match conditions
+                .iter()
+                .position(|c| matches!(c, Condition::MeasureAbove(_)))
+            {
+                Some(i) => {
+                    let min = match conditions.remove(i) {
+                        Condition::MeasureAbove(min) => min,
+                        _ => unreachable!(),
+                    };
+                    let condition = match conditions.len() {
+                        1 => conditions.pop(),
+                        _ => Some(Condition::All(conditions)),
+                    };
+                    (Some(min), condition)
+                }
+                None => (None, Some(Condition::All(conditions))),
+            }
+        }
+        condition => (None, condition),
+    };
+    Ok(SuffixRule {
+        suffix: suffix.to_string().into(),
+        replacement: replacement.to_string().into(),
+        min_measure,
+        condition,
+        enabled: true,
+    })
+}
+
+// The index in `text` of the ')' closing a '(' just before it.
+fn closing_parenthesis(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Atom(Condition),
+}
+
+fn lex(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '*' => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !c.is_ascii_alphabetic() && c != '*' {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                Token::Atom(match name.as_str() {
+                    "v*" => Condition::HasVowel,
+                    "d" => Condition::EndsWithDoubleConsonant,
+                    "o" => Condition::EndsCvc,
+                    letters
+                        if !letters.is_empty()
+                            && letters.bytes().all(|b| b.is_ascii_uppercase()) =>
+                    {
+                        Condition::EndsWithAnyOf(letters.to_ascii_lowercase().into())
+                    }
+                    _ => return Err(format!("unknown condition *{}", name)),
+                })
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                    word.push(c);
+                    chars.next();
+                }
+                match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "m" => {
+                        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                            chars.next();
+                        }
+                        let op = chars.next();
+                        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                            chars.next();
+                        }
+                        let mut digits = String::new();
+                        while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                            digits.push(c);
+                            chars.next();
+                        }
+                        let n = digits
+                            .parse()
+                            .map_err(|_| "expected a number after m> or m=".to_string())?;
+                        match op {
+                            Some('>') => Token::Atom(Condition::MeasureAbove(n)),
+                            Some('=') => Token::Atom(Condition::MeasureEquals(n)),
+                            _ => return Err("expected m>N or m=N".to_string()),
+                        }
+                    }
+                    _ => return Err(format!("unknown condition {:?}", word)),
+                }
+            }
+            c => return Err(format!("unexpected {:?} in condition", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn parse_condition(text: &str) -> Result<Condition, String> {
+    let tokens = lex(text)?;
+    let mut position = 0;
+    let condition = parse_or(&tokens, &mut position)?;
+    match tokens.get(position) {
+        None => Ok(condition),
+        Some(_) => Err("expected and or or between conditions".to_string()),
+    }
+}
+
+fn parse_or(tokens: &[Token], position: &mut usize) -> Result<Condition, String> {
+    let mut conditions = vec![parse_and(tokens, position)?];
+    while tokens.get(*position) == Some(&Token::Or) {
+        *position += 1;
+        conditions.push(parse_and(tokens, position)?);
+    }
+    Ok(match conditions.len() {
+        1 => conditions.remove(0),
+        _ => Condition::Any(conditions),
+    })
+}
+
+fn parse_and(tokens: &[Token], position: &mut usize) -> Result<Condition, String> {
+    let mut conditions = vec![parse_unary(tokens, position)?];
+    while tokens.get(*position) == Some(&Token::And) {
+        *position += 1;
+        conditions.push(parse_unary(tokens, position)?);
+    }
+    Ok(match conditions.len() {
+        1 => conditions.remove(0),
+        _ => Condition::All(conditions),
+    })
+}
+
+fn parse_unary(tokens: &[Token], position: &mut usize) -> Result<Condition, String> {
+    let token = tokens.get(*position).ok_or("expected a condition")?;
+    *position += 1;
+    match token {
+        Token::Not => Ok(Condition::Not(Box::new(parse_unary(tokens, position)?))),
+        Token::Open => {
+            let condition = parse_or(tokens, position)?;
+            match tokens.get(*position) {
+                Some(Token::Close) => {
+                    *position += 1;
+                    Ok(condition)
+                }
+                _ => Err("unbalanced parentheses".to_string()),
+            }
+        }
+        Token::Atom(condition) => Ok(condition.clone()),
+        _ => Err("expected a condition".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEDICAL: &str = "
+        # Plurals, then medical suffixes.
+        step plural
+            sses -> ss
+            ies -> i
+            ss -> ss
+            is -> is
+            (m>0) s ->
+        step medical
+            (m>0) ectomy ->
+            (m>0 and not *o) itis ->
+            (m>1 and (*S or *T)) ion ->
+    ";
+
+    #[test]
+    fn test_parse() {
+        let stemmer = RuleStemmer::parse(MEDICAL).unwrap();
+        let names: Vec<&str> = stemmer.steps().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["plural", "medical"]);
+        let rules = &stemmer.steps()[1].rules;
+        assert_eq!(rules[0].min_measure, Some(0));
+        assert_eq!(rules[0].condition, None);
+        assert_eq!(
+            rules[1].condition,
+            Some(Condition::Not(Box::new(Condition::EndsCvc)))
+        );
+        assert_eq!(
+            rules[2].condition,
+            Some(Condition::Any(vec![
+                Condition::EndsWithAnyOf("s".into()),
+                Condition::EndsWithAnyOf("t".into()),
+            ]))
+        );
+        assert_eq!(stemmer.steps()[0].rules[0].min_measure, None);
+        assert_eq!(MEDICAL.parse::<RuleStemmer>(), Ok(stemmer));
+    }
+
+    #[test]
+    fn test_stem() -> Result<(), StemError> {
+        let stemmer = RuleStemmer::parse(MEDICAL).unwrap();
+        assert_eq!(stemmer.stem("Appendectomies")?, "appendectomi");
+        assert_eq!(stemmer.stem("appendectomy")?, "append");
+        assert_eq!(stemmer.stem("arthritis")?, "arthr");
+        assert_eq!(stemmer.stem("colitis")?, "colitis");
+        assert_eq!(stemmer.stem("infections")?, "infect");
+        assert_eq!(stemmer.stem("is")?, "is");
+        assert_eq!(stemmer.stem("glass")?, "glass");
+        assert!(stemmer.stem("caf\u{e9}").is_err());
+        assert_eq!(RuleStemmer::default().stem("Cats")?, "cats");
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |source: &str| RuleStemmer::parse(source).unwrap_err();
+        assert_eq!(
+            error("s ->").to_string(),
+            "line 1: rule before the first step"
+        );
+        assert_eq!(error("step a\n\nstep").line(), 3);
+        assert_eq!(
+            error("step a b").message(),
+            "unexpected \"b\" after step name"
+        );
+        assert_eq!(error("step a\n s - x").line(), 2);
+        assert_eq!(
+            error("step a\n (m>0 s ->").message(),
+            "unbalanced parentheses"
+        );
+        assert_eq!(
+            error("step a\n (m>) s ->").message(),
+            "expected a number after m> or m="
+        );
+        assert_eq!(
+            error("step a\n (*q) s ->").message(),
+            "unknown condition *q"
+        );
+        assert_eq!(
+            error("step a\n (*v* and) s ->").message(),
+            "expected a condition"
+        );
+        assert_eq!(
+            error("step a\n (*v* *d) s ->").message(),
+            "expected and or or between conditions"
+        );
+        assert_eq!(
+            error("step a\n (m>0) S ->").message(),
+            "\"S\" is not all lowercase letters"
+        );
+        assert_eq!(
+            error("step a\n (m>0) -> e").message(),
+            "rule without a suffix"
+        );
+    }
+}
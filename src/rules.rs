@@ -0,0 +1,282 @@
+// The suffix rules of steps 2 to 4 as data. A step tries its rules in order
+// and applies the first one whose suffix (and condition, if any) matches the
+// word, provided the measure of the stem in front of the suffix is greater
+// than the rule's minimum. As in Porter's reference implementation, a rule
+// that matches but fails the measure test ends the step.
+
+use std::borrow::Cow;
+
+use crate::{Rule, Word};
+
+/// A condition on the stem left once a rule's suffix is removed, checked on
+/// top of its measure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// The stem ends with one of these letters.
+    EndsWithAnyOf(Cow<'static, str>),
+    /// The stem contains a vowel (*v*).
+    HasVowel,
+    /// The stem ends with a double consonant (*d).
+    EndsWithDoubleConsonant,
+    /// The stem ends consonant-vowel-consonant, where the last consonant is
+    /// not W, X or Y (*o).
+    EndsCvc,
+    /// The measure of the stem is greater than this.
+    MeasureAbove(usize),
+    /// The measure of the stem is this.
+    MeasureEquals(usize),
+    /// The condition does not hold.
+    Not(Box<Condition>),
+    /// All of the conditions hold.
+    All(Vec<Condition>),
+    /// At least one of the conditions holds.
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    // Whether the condition holds for the first `len` letters of `word`.
+    pub(crate) fn holds(&self, word: &Word, len: usize) -> bool {
+        match self {
+            Condition::EndsWithAnyOf(letters) => word
+                .get_char_at_position(len)
+                .is_some_and(|last| letters.as_bytes().contains(&last)),
+            Condition::HasVowel => word.has_vowel_with_limit(len),
+            Condition::EndsWithDoubleConsonant => word.ends_with_double_consonant_with_limit(len),
+            Condition::EndsCvc => word.ends_cvc_with_limit(len),
+            Condition::MeasureAbove(min) => word.measure_with_limit(len) > *min,
+            Condition::MeasureEquals(measure) => word.measure_with_limit(len) == *measure,
+            Condition::Not(condition) => !condition.holds(word, len),
+            Condition::All(conditions) => conditions.iter().all(|c| c.holds(word, len)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.holds(word, len)),
+        }
+    }
+}
+
+/// A rule replacing `suffix` with `replacement` when the measure of the stem
+/// in front of the suffix is greater than `min_measure`, if there is one, and
+/// `condition`, if any, holds for the stem. A suffix that is the whole word
+/// matches too, and ends the step: the empty stem fails the measure test, and
+/// a rule without one does not apply to it either.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuffixRule {
+    pub suffix: Cow<'static, str>,
+    pub replacement: Cow<'static, str>,
+    pub min_measure: Option<usize>,
+    pub condition: Option<Condition>,
+    /// A disabled rule is skipped as if it were not in the table.
+    pub enabled: bool,
+}
+
+impl SuffixRule {
+    pub const fn new(
+        suffix: &'static str,
+        replacement: &'static str,
+        min_measure: usize,
+    ) -> SuffixRule {
+        SuffixRule {
+            suffix: Cow::Borrowed(suffix),
+            replacement: Cow::Borrowed(replacement),
+            min_measure: Some(min_measure),
+            condition: None,
+            enabled: true,
+        }
+    }
+
+    fn matches(&self, word: &Word) -> bool {
+        // Most rules are ruled out by the last letter alone.
+        let last = self.suffix.as_bytes().last();
+        self.enabled
+            && last.map_or(true, |&last| word.last() == Some(last))
+            && word.len() >= self.suffix.len()
+            && word.ends_with(&self.suffix)
+            && self.condition.as_ref().map_or(true, |condition| {
+                condition.holds(word, word.len() - self.suffix.len())
+            })
+    }
+}
+
+/// The rules of step 2. -bli and -logi are the departures from the paper's
+/// -abli that Porter made in his reference implementation.
+pub const STEP2: &[SuffixRule] = &[
+    SuffixRule::new("ational", "ate", 0),
+    SuffixRule::new("tional", "tion", 0),
+    SuffixRule::new("enci", "ence", 0),
+    SuffixRule::new("anci", "ance", 0),
+    SuffixRule::new("izer", "ize", 0),
+    SuffixRule::new("logi", "log", 0),
+    SuffixRule::new("bli", "ble", 0),
+    SuffixRule::new("alli", "al", 0),
+    SuffixRule::new("entli", "ent", 0),
+    SuffixRule::new("eli", "e", 0),
+    SuffixRule::new("ousli", "ous", 0),
+    SuffixRule::new("ization", "ize", 0),
+    SuffixRule::new("ation", "ate", 0),
+    SuffixRule::new("ator", "ate", 0),
+    SuffixRule::new("alism", "al", 0),
+    SuffixRule::new("iveness", "ive", 0),
+    SuffixRule::new("fulness", "ful", 0),
+    SuffixRule::new("ousness", "ous", 0),
+    SuffixRule::new("aliti", "al", 0),
+    SuffixRule::new("iviti", "ive", 0),
+    SuffixRule::new("biliti", "ble", 0),
+];
+
+/// The rules of step 3.
+pub const STEP3: &[SuffixRule] = &[
+    SuffixRule::new("icate", "ic", 0),
+    SuffixRule::new("ative", "", 0),
+    SuffixRule::new("alize", "al", 0),
+    SuffixRule::new("iciti", "ic", 0),
+    SuffixRule::new("ical", "ic", 0),
+    SuffixRule::new("ful", "", 0),
+    SuffixRule::new("ness", "", 0),
+];
+
+/// The rules of step 4. -ion is only removed after an s or a t, which stays
+/// part of the stem.
+pub const STEP4: &[SuffixRule] = &[
+    SuffixRule::new("al", "", 1),
+    SuffixRule::new("ance", "", 1),
+    SuffixRule::new("ence", "", 1),
+    SuffixRule::new("er", "", 1),
+    SuffixRule::new("ic", "", 1),
+    SuffixRule::new("able", "", 1),
+    SuffixRule::new("ible", "", 1),
+    SuffixRule::new("ant", "", 1),
+    SuffixRule::new("ement", "", 1),
+    SuffixRule::new("ment", "", 1),
+    SuffixRule::new("ent", "", 1),
+    SuffixRule {
+        suffix: Cow::Borrowed("ion"),
+        replacement: Cow::Borrowed(""),
+        min_measure: Some(1),
+        condition: Some(Condition::EndsWithAnyOf(Cow::Borrowed("st"))),
+        enabled: true,
+    },
+    SuffixRule::new("ou", "", 1),
+    SuffixRule::new("ism", "", 1),
+    SuffixRule::new("ate", "", 1),
+    SuffixRule::new("iti", "", 1),
+    SuffixRule::new("ous", "", 1),
+    SuffixRule::new("ive", "", 1),
+    SuffixRule::new("ize", "", 1),
+];
+
+/// The rule tables a `Stemmer` runs in steps 2 to 4. The default tables are
+/// `STEP2`, `STEP3` and `STEP4`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleTables {
+    pub step2: Vec<SuffixRule>,
+    pub step3: Vec<SuffixRule>,
+    pub step4: Vec<SuffixRule>,
+}
+
+impl Default for RuleTables {
+    fn default() -> RuleTables {
+        RuleTables {
+            step2: STEP2.to_vec(),
+            step3: STEP3.to_vec(),
+            step4: STEP4.to_vec(),
+        }
+    }
+}
+
+impl RuleTables {
+    /// Enables or disables every rule for `suffix` in the three tables, and
+    /// returns whether there was one.
+    pub fn set_enabled(&mut self, suffix: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for rule in self
+            .step2
+            .iter_mut()
+            .chain(&mut self.step3)
+            .chain(&mut self.step4)
+            .filter(|rule| rule.suffix == suffix)
+        {
+            rule.enabled = enabled;
+            found = true;
+        }
+        found
+    }
+}
+
+// Applies the first rule in `rules` that matches the word.
+pub(crate) fn apply(word: &mut Word, rules: &[SuffixRule]) -> Option<Rule> {
+    let rule = rules.iter().find(|rule| rule.matches(word))?;
+    let stem_len = word.len() - rule.suffix.len();
+    let measure = match rule.min_measure {
+        Some(min) => match word.measure_with_limit(stem_len) {
+            measure if measure > min => Some(measure),
+            _ => return None,
+        },
+        None if stem_len == 0 => return None,
+        None => None,
+    };
+    word.truncate(stem_len);
+    word.push_str(&rule.replacement);
+    Some(Rule::new(&rule.suffix, &rule.replacement, measure))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply_to(rules: &[SuffixRule], word: &str) -> String {
+        let mut word = Word::from(word);
+        apply(&mut word, rules);
+        word.as_str().to_string()
+    }
+
+    #[test]
+    fn test_first_match_decides() {
+        // -ement matches, so -ment and -ent are not tried although the
+        // measure of "rep" is too small.
+        assert_eq!(apply_to(STEP4, "repement"), "repement");
+        assert_eq!(apply_to(STEP4, "replacement"), "replac");
+        assert_eq!(apply_to(STEP4, "adoption"), "adopt");
+        assert_eq!(apply_to(STEP4, "religion"), "religion");
+    }
+
+    #[test]
+    fn test_whole_word() {
+        // -ization is the whole word, so the step ends there rather than
+        // going on to -ation, whose stem "iz" has a measure of 1.
+        assert_eq!(apply_to(STEP2, "ization"), "ization");
+        assert_eq!(apply_to(STEP2, "organization"), "organize");
+        let rules = [SuffixRule {
+            min_measure: None,
+            ..SuffixRule::new("itis", "", 0)
+        }];
+        assert_eq!(apply_to(&rules, "itis"), "itis");
+        assert_eq!(apply_to(&rules, "colitis"), "col");
+    }
+
+    #[test]
+    fn test_edit_tables() {
+        let mut tables = RuleTables::default();
+        assert!(tables.set_enabled("ement", false));
+        assert!(!tables.set_enabled("xyz", false));
+        assert_eq!(apply_to(&tables.step4, "replacement"), "replace");
+
+        tables.step3.push(SuffixRule {
+            condition: Some(Condition::Not(Box::new(Condition::EndsCvc))),
+            ..SuffixRule::new("itis", "", 0)
+        });
+        assert_eq!(apply_to(&tables.step3, "arthritis"), "arthr");
+        assert_eq!(apply_to(&tables.step3, "bronchitis"), "bronch");
+        assert_eq!(apply_to(&tables.step3, "colitis"), "colitis");
+    }
+
+    #[test]
+    fn test_conditions() {
+        let word = Word::from("hopping");
+        assert!(Condition::EndsWithDoubleConsonant.holds(&word, 4));
+        assert!(!Condition::EndsWithDoubleConsonant.holds(&word, 3));
+        assert!(Condition::EndsCvc.holds(&word, 3));
+        assert!(Condition::HasVowel.holds(&word, 2));
+        assert!(!Condition::HasVowel.holds(&word, 1));
+        let st = Condition::EndsWithAnyOf(Cow::Borrowed("st"));
+        assert!(!st.holds(&word, 0));
+        assert!(!st.holds(&word, 3));
+    }
+}
@@ -0,0 +1,95 @@
+// The S-stemmer, from D. Harman, "How Effective is Suffixing?", JASIS 42
+// (1991). It only conflates singular and plural forms, applying the first of
+// three rules that matches:
+//
+//     -ies, but not -eies or -aies: -ies becomes -y
+//     -es, but not -aes, -ees or -oes: -es becomes -e
+//     -s, but not -us or -ss: -s is removed
+
+use std::borrow::Cow;
+
+use crate::{validate, Stem, StemError};
+
+/// Harman's S-stemmer, as run by `stem`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SStemmer;
+
+impl Stem for SStemmer {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        stem(word)
+    }
+}
+
+/// Stems `word` with the S-stemmer. The input is validated, trimmed and
+/// lowercased the same way as for the Porter `stem`, and words of one or two
+/// letters are returned as they are.
+///
+/// The stem is borrowed from `word` unless -ies became -y or the word needed
+/// lowercasing.
+pub fn stem(word: &str) -> Result<Cow<'_, str>, StemError> {
+    validate(word)?;
+    if word.len() <= 2 {
+        return Ok(Cow::Borrowed(word));
+    }
+    let word = word.trim();
+    let mut stem = if word.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(word.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(word)
+    };
+    let (remove, append) = match rule(&stem) {
+        Some(rule) => rule,
+        None => return Ok(stem),
+    };
+    let len = stem.len() - remove;
+    match &mut stem {
+        Cow::Borrowed(word) if append.is_empty() => *word = &word[..len],
+        stem => {
+            let stem = stem.to_mut();
+            stem.truncate(len);
+            stem.push_str(append);
+        }
+    }
+    Ok(stem)
+}
+
+// The number of letters to remove from the end of `word` and the letters to
+// append, if a rule applies. -es becoming -e is removing the s.
+fn rule(word: &str) -> Option<(usize, &'static str)> {
+    let ends_with_any = |suffixes: &[&str]| suffixes.iter().any(|s| word.ends_with(s));
+    if word.ends_with("ies") {
+        if !ends_with_any(&["eies", "aies"]) {
+            return Some((3, "y"));
+        }
+    } else if word.ends_with("es") {
+        if !ends_with_any(&["aes", "ees", "oes"]) {
+            return Some((1, ""));
+        }
+    } else if word.ends_with('s') && !ends_with_any(&["us", "ss"]) {
+        return Some((1, ""));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stem() -> Result<(), StemError> {
+        assert_eq!(stem("ponies")?, "pony");
+        assert_eq!(stem("Cities")?, "city");
+        assert_eq!(stem("boxes")?, "boxe");
+        assert_eq!(stem("cats")?, "cat");
+        assert_eq!(stem("corpus")?, "corpus");
+        assert_eq!(stem("glass")?, "glass");
+        assert_eq!(stem("toes")?, "toes");
+        assert_eq!(stem("zombies")?, "zomby");
+        assert_eq!(stem("running")?, "running");
+        assert_eq!(stem("is")?, "is");
+        assert!(matches!(stem(" tags ")?, Cow::Borrowed("tag")));
+        assert!(matches!(stem("TAGS")?, Cow::Owned(_)));
+        assert!(stem("").is_err());
+        Ok(())
+    }
+}
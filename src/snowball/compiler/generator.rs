@@ -0,0 +1,929 @@
+// Writing a parsed Snowball program out as Rust.
+//
+// Each command is generated with the statements to run when it fails: a
+// `return false`, or a `break` out of the labelled block of an enclosing
+// command that recovers from the failure. Generating a command also tells
+// whether it can succeed, so that nothing is written after one that cannot,
+// and a label or saved cursor that its code never refers to is left out. The
+// generated code has no unreachable statements or unused names, and builds
+// without warnings.
+//
+// A cursor saved to be restored later is an offset from the start of the word
+// going forwards, and from the limit going backwards, where it stays right
+// when text behind the cursor is replaced. Only the routines that the
+// externals can reach are generated, along with the groupings, tables and
+// variables those use.
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::parser::{Among, Command, Expr, Kind, Program, Text};
+use super::CompileError;
+
+const INDENT: &str = "    ";
+
+// Lines of code, each with its depth of indentation.
+#[derive(Default)]
+struct Code {
+    lines: Vec<(usize, String)>,
+}
+
+impl Code {
+    fn line<S: Into<String>>(&mut self, line: S) {
+        self.lines.push((0, line.into()));
+    }
+
+    fn lines(&mut self, lines: &[String]) {
+        for line in lines {
+            self.line(line.as_str());
+        }
+    }
+
+    fn append(&mut self, code: Code) {
+        self.lines.extend(code.lines);
+    }
+
+    // `open`, then `body` indented one more level, then `close`.
+    fn block(&mut self, open: String, body: Code, close: &str) {
+        self.line(open);
+        self.lines.extend(
+            body.lines
+                .into_iter()
+                .map(|(depth, line)| (depth + 1, line)),
+        );
+        self.line(close);
+    }
+
+    // `if condition {`, the statements, `}`.
+    fn check(&mut self, condition: String, fail: &[String]) {
+        let mut body = Code::default();
+        body.lines(fail);
+        self.block(format!("if {} {{", condition), body, "}");
+    }
+
+    // Whether any line refers to `name`.
+    fn mentions(&self, name: &str) -> bool {
+        self.lines.iter().any(|(_, line)| {
+            line.match_indices(name).any(|(i, _)| {
+                let after = line[i + name.len()..].chars().next();
+                let before = line[..i].chars().next_back();
+                !matches!(after, Some(c) if c.is_alphanumeric() || c == '_')
+                    && !matches!(before, Some(c) if c.is_alphanumeric() || c == '_')
+            })
+        })
+    }
+
+    fn write(&self, depth: usize, out: &mut String) {
+        for (indent, line) in &self.lines {
+            if !line.is_empty() {
+                out.push_str(&INDENT.repeat(depth + indent));
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+}
+
+struct Generator<'a> {
+    program: &'a Program,
+    backward: HashMap<&'a str, bool>,
+    // The among tables, in the order they are met, and the direction of each.
+    tables: Vec<(usize, bool)>,
+    groupings: BTreeSet<&'a str>,
+    variables: BTreeSet<&'a str>,
+    // For the routine being generated: a counter for fresh names, how often
+    // it sets among_var and whether it does so in a loop.
+    counter: usize,
+    among_vars: usize,
+    loops: usize,
+    among_var_in_loop: bool,
+}
+
+pub(super) fn generate(program: &Program) -> Result<String, CompileError> {
+    let mut generator = Generator {
+        program,
+        backward: program
+            .routines
+            .iter()
+            .map(|routine| (routine.name.as_str(), routine.backward))
+            .collect(),
+        tables: Vec::new(),
+        groupings: BTreeSet::new(),
+        variables: BTreeSet::new(),
+        counter: 0,
+        among_vars: 0,
+        loops: 0,
+        among_var_in_loop: false,
+    };
+    let externals: Vec<&str> = program
+        .declarations
+        .iter()
+        .filter(|declaration| declaration.kind == Kind::External)
+        .map(|declaration| declaration.name.as_str())
+        .collect();
+    let reachable = generator.reachable(&externals);
+
+    let mut routines = Code::default();
+    for routine in &program.routines {
+        if !reachable.contains(routine.name.as_str()) {
+            continue;
+        }
+        generator.counter = 0;
+        generator.among_vars = 0;
+        generator.among_var_in_loop = false;
+        let mut body = Code::default();
+        if generator.command(
+            &routine.body,
+            routine.backward,
+            &["return false;".to_string()],
+            &mut body,
+        )? {
+            body.line("true");
+        }
+        let mut code = Code::default();
+        match generator.among_vars {
+            0 => {}
+            1 if !generator.among_var_in_loop => code.line("let among_var;"),
+            _ => code.line("let mut among_var;"),
+        }
+        code.append(body);
+        let env = if code.mentions("env") { "env" } else { "_env" };
+        let context = if code.mentions("context") {
+            "context"
+        } else {
+            "_context"
+        };
+        routines.line("");
+        if has_uppercase(&routine.name) {
+            routines.line("#[allow(non_snake_case)]");
+        }
+        routines.block(
+            format!(
+                "fn r_{}({}: &mut Env, {}: &mut Context) -> bool {{",
+                routine.name, env, context
+            ),
+            code,
+            "}",
+        );
+    }
+
+    let mut out = String::new();
+    out.push_str("// Generated from a Snowball source by stemmer::snowball::compile.\n\n");
+    if generator.tables.is_empty() {
+        out.push_str("use stemmer::snowball::runtime::Env;\n");
+    } else {
+        out.push_str("use stemmer::snowball::runtime::{Among, Env};\n");
+    }
+    out.push('\n');
+    generator.context(&mut out);
+    for &(index, backward) in &generator.tables {
+        generator.table(index, backward)?.write(0, &mut out);
+    }
+    for grouping in &program.groupings {
+        if generator.groupings.contains(grouping.name.as_str()) {
+            grouping_fn(&grouping.name, &grouping.chars).write(0, &mut out);
+        }
+    }
+    routines.write(0, &mut out);
+    for external in externals {
+        out.push_str(&format!(
+            "\npub fn {}(env: &mut Env) -> bool {{\n{}let mut context = Context::default();\n{}r_{}(env, &mut context)\n}}\n",
+            external, INDENT, INDENT, external
+        ));
+    }
+    Ok(out)
+}
+
+fn has_uppercase(name: &str) -> bool {
+    name.chars().any(|c| c.is_ascii_uppercase())
+}
+
+fn grouping_fn(name: &str, chars: &[char]) -> Code {
+    // Runs of three or more consecutive characters become ranges.
+    let mut patterns = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let mut j = i;
+        while j + 1 < chars.len() && chars[j + 1] as u32 == chars[j] as u32 + 1 {
+            j += 1;
+        }
+        if j >= i + 2 {
+            patterns.push(format!("{:?}..={:?}", chars[i], chars[j]));
+            i = j + 1;
+        } else {
+            patterns.push(format!("{:?}", chars[i]));
+            i += 1;
+        }
+    }
+    let mut code = Code::default();
+    code.line("");
+    if has_uppercase(name) {
+        code.line("#[allow(non_snake_case)]");
+    }
+    let mut body = Code::default();
+    body.line(format!("matches!(c, {})", patterns.join(" | ")));
+    code.block(format!("fn g_{}(c: char) -> bool {{", name), body, "}");
+    code
+}
+
+// The code of an expression, with its precedence: 5 for an operand, 4 for a
+// negation, 3 for a cast, 2 for * and /, 1 for + and -.
+fn expression(expr: &Expr, backward: bool, variables: &mut BTreeSet<String>) -> (String, u8) {
+    match expr {
+        Expr::Number(n) => (n.to_string(), 5),
+        Expr::Variable(name) => {
+            variables.insert(name.clone());
+            (format!("context.i_{}", name), 5)
+        }
+        Expr::Cursor => ("env.cursor as i32".to_string(), 3),
+        Expr::Limit if backward => ("env.limit_backward as i32".to_string(), 3),
+        Expr::Limit => ("env.limit as i32".to_string(), 3),
+        Expr::Size => ("env.size()".to_string(), 5),
+        Expr::Len => ("env.length()".to_string(), 5),
+        Expr::Sizeof(name) => {
+            variables.insert(name.clone());
+            (format!("context.s_{}.len() as i32", name), 3)
+        }
+        Expr::Lenof(name) => {
+            variables.insert(name.clone());
+            (format!("context.s_{}.chars().count() as i32", name), 3)
+        }
+        Expr::Maxint => ("i32::MAX".to_string(), 5),
+        Expr::Minint => ("i32::MIN".to_string(), 5),
+        Expr::Negate(operand) => {
+            let operand = expression(operand, backward, variables);
+            (format!("-{}", parenthesize(operand, 4)), 4)
+        }
+        Expr::Binary(left, operator, right) => {
+            let precedence = if matches!(*operator, "*" | "/") { 2 } else { 1 };
+            let left = parenthesize(expression(left, backward, variables), precedence);
+            let right = parenthesize(expression(right, backward, variables), precedence + 1);
+            (format!("{} {} {}", left, operator, right), precedence)
+        }
+    }
+}
+
+fn parenthesize((code, precedence): (String, u8), at_least: u8) -> String {
+    if precedence < at_least {
+        format!("({})", code)
+    } else {
+        code
+    }
+}
+
+fn negate(comparison: &str) -> &'static str {
+    match comparison {
+        "==" => "!=",
+        "!=" => "==",
+        ">" => "<=",
+        ">=" => "<",
+        "<" => ">=",
+        _ => ">",
+    }
+}
+
+impl<'a> Generator<'a> {
+    fn fresh(&mut self, prefix: &str) -> String {
+        self.counter += 1;
+        format!("{}_{}", prefix, self.counter)
+    }
+
+    // The routines that the externals call, directly or not.
+    fn reachable(&self, externals: &[&'a str]) -> BTreeSet<&'a str> {
+        let mut reachable = BTreeSet::new();
+        let mut pending = externals.to_vec();
+        while let Some(name) = pending.pop() {
+            if !reachable.insert(name) {
+                continue;
+            }
+            if let Some(routine) = self.program.routines.iter().find(|r| r.name == name) {
+                self.calls(&routine.body, &mut pending);
+            }
+        }
+        reachable
+    }
+
+    fn calls(&self, command: &'a Command, calls: &mut Vec<&'a str>) {
+        match command {
+            Command::List(commands) | Command::Or(commands) | Command::And(commands) => {
+                for command in commands {
+                    self.calls(command, calls);
+                }
+            }
+            Command::Not(command)
+            | Command::Test(command)
+            | Command::Try(command)
+            | Command::Do(command)
+            | Command::Fail(command)
+            | Command::Repeat(command)
+            | Command::Backwards(command)
+            | Command::Goto(command)
+            | Command::Gopast(command)
+            | Command::Loop(_, command)
+            | Command::Atleast(_, command) => self.calls(command, calls),
+            Command::Setlimit(limit, command) => {
+                self.calls(limit, calls);
+                self.calls(command, calls);
+            }
+            Command::Substring(index) => {
+                self.among_calls(&self.program.amongs[self.program.substrings[*index]], calls)
+            }
+            Command::Among(index) => {
+                let among = &self.program.amongs[*index];
+                if !among.has_substring {
+                    self.among_calls(among, calls);
+                }
+                for command in among.starter.iter().chain(&among.commands) {
+                    self.calls(command, calls);
+                }
+            }
+            Command::Call(name, _) => calls.push(name),
+            _ => {}
+        }
+    }
+
+    fn among_calls(&self, among: &'a Among, calls: &mut Vec<&'a str>) {
+        calls.extend(among.strings.iter().filter_map(|s| s.condition.as_deref()));
+    }
+
+    fn context(&self, out: &mut String) {
+        let mut fields = Code::default();
+        for declaration in &self.program.declarations {
+            if !self.variables.contains(declaration.name.as_str()) {
+                continue;
+            }
+            let (prefix, rust) = match declaration.kind {
+                Kind::String => ("s", "String"),
+                Kind::Integer => ("i", "i32"),
+                _ => ("b", "bool"),
+            };
+            fields.line(format!("{}_{}: {},", prefix, declaration.name, rust));
+        }
+        if fields.lines.iter().any(|(_, field)| has_uppercase(field)) {
+            out.push_str("#[allow(non_snake_case)]\n");
+        }
+        out.push_str("#[derive(Default)]\n");
+        let mut code = Code::default();
+        code.block("struct Context {".to_string(), fields, "}");
+        code.write(0, out);
+    }
+
+    fn table(&self, index: usize, backward: bool) -> Result<Code, CompileError> {
+        let among = &self.program.amongs[index];
+        let key = |s: &str| -> Vec<u8> {
+            let mut key = s.as_bytes().to_vec();
+            if backward {
+                key.reverse();
+            }
+            key
+        };
+        let mut strings: Vec<_> = among.strings.iter().collect();
+        strings.sort_by_key(|s| key(&s.string));
+        let mut body = Code::default();
+        for (i, s) in strings.iter().enumerate() {
+            let substring = (0..i).rev().find(|&j| {
+                let shorter = &strings[j].string;
+                if backward {
+                    s.string.ends_with(shorter.as_str())
+                } else {
+                    s.string.starts_with(shorter.as_str())
+                }
+            });
+            let condition = match &s.condition {
+                Some(name) => {
+                    self.check_mode(name, backward, s.line)?;
+                    format!("Some(r_{})", name)
+                }
+                None => "None".to_string(),
+            };
+            let substring = match substring {
+                Some(j) => format!("Some({})", j),
+                None => "None".to_string(),
+            };
+            body.line(format!(
+                "Among {{ string: {:?}, substring: {}, result: {}, condition: {} }},",
+                s.string, substring, s.result, condition
+            ));
+        }
+        let mut code = Code::default();
+        code.line("");
+        code.block(
+            format!("static A_{}: &[Among<Context>] = &[", index),
+            body,
+            "];",
+        );
+        Ok(code)
+    }
+
+    fn check_mode(&self, name: &str, backward: bool, line: usize) -> Result<(), CompileError> {
+        let routine_backward = self.backward[name];
+        if routine_backward == backward {
+            return Ok(());
+        }
+        let (mode, called) = if routine_backward {
+            ("backward", "forwards")
+        } else {
+            ("forward", "backwards")
+        };
+        Err(CompileError::new(
+            line,
+            format!("{} is a {} routine called {}", name, mode, called),
+        ))
+    }
+
+    fn expr(&mut self, e: &'a Expr, backward: bool) -> (String, u8) {
+        let mut variables = BTreeSet::new();
+        let code = expression(e, backward, &mut variables);
+        for name in variables {
+            if let Some(declaration) = self.program.declarations.iter().find(|d| d.name == name) {
+                self.variables.insert(&declaration.name);
+            }
+        }
+        code
+    }
+
+    fn variable(&mut self, name: &'a str) {
+        self.variables.insert(name);
+    }
+
+    fn save(backward: bool) -> &'static str {
+        if backward {
+            "env.limit - env.cursor"
+        } else {
+            "env.cursor"
+        }
+    }
+
+    fn restore(saved: &str, backward: bool) -> String {
+        if backward {
+            format!("env.cursor = env.limit - {};", saved)
+        } else {
+            format!("env.cursor = {};", saved)
+        }
+    }
+
+    fn text(&mut self, text: &'a Text) -> String {
+        match text {
+            Text::Literal(s) => format!("{:?}", s),
+            Text::Variable(name) => {
+                self.variable(name);
+                format!("&context.s_{}", name)
+            }
+        }
+    }
+
+    // Finds the string of an among, setting among_var if it has commands
+    // to choose from.
+    fn find(&mut self, index: usize, backward: bool, fail: &[String], code: &mut Code) {
+        if !self.tables.iter().any(|&(i, _)| i == index) {
+            self.tables.push((index, backward));
+        }
+        let function = if backward {
+            "find_among_b"
+        } else {
+            "find_among"
+        };
+        let call = format!("env.{}(A_{}, context)", function, index);
+        if self.dispatches(&self.program.amongs[index]) {
+            self.among_vars += 1;
+            self.among_var_in_loop |= self.loops > 0;
+            code.line(format!("among_var = {};", call));
+            code.check("among_var == 0".to_string(), fail);
+        } else {
+            code.check(format!("{} == 0", call), fail);
+        }
+    }
+
+    // Whether an among has more than one command to choose between.
+    fn dispatches(&self, among: &Among) -> bool {
+        among.commands.len() > 1 || among.strings.iter().any(|s| s.result != 1)
+    }
+
+    // Generates `command`, running the statements of `fail` when it fails,
+    // and returns whether it can succeed.
+    fn command(
+        &mut self,
+        command: &'a Command,
+        backward: bool,
+        fail: &[String],
+        code: &mut Code,
+    ) -> Result<bool, CompileError> {
+        let next = if backward {
+            "env.previous_char()"
+        } else {
+            "env.next_char()"
+        };
+        let suffix = if backward { "_b" } else { "" };
+        match command {
+            Command::List(commands) => {
+                for command in commands {
+                    if !self.command(command, backward, fail, code)? {
+                        return Ok(false);
+                    }
+                }
+            }
+            Command::Or(commands) => return self.or(commands, backward, fail, code),
+            Command::And(commands) => {
+                let saved = self.fresh("v");
+                let mut body = Code::default();
+                for (i, command) in commands.iter().enumerate() {
+                    if i > 0 {
+                        body.line(Self::restore(&saved, backward));
+                    }
+                    if !self.command(command, backward, fail, &mut body)? {
+                        code.append(body);
+                        return Ok(false);
+                    }
+                }
+                if body.mentions(&saved) {
+                    code.line(format!("let {} = {};", saved, Self::save(backward)));
+                }
+                code.append(body);
+            }
+            Command::Not(command) => {
+                let (label, saved) = (self.fresh("'lab"), self.fresh("v"));
+                let mut inner = Code::default();
+                let ok = self.command(
+                    command,
+                    backward,
+                    &[format!("break {};", label)],
+                    &mut inner,
+                )?;
+                if !inner.mentions(&label) {
+                    code.append(inner);
+                    if ok {
+                        code.lines(fail);
+                    }
+                    return Ok(false);
+                }
+                if ok {
+                    inner.lines(fail);
+                }
+                code.line(format!("let {} = {};", saved, Self::save(backward)));
+                code.block(format!("{}: {{", label), inner, "}");
+                code.line(Self::restore(&saved, backward));
+            }
+            Command::Try(command) => {
+                let (label, saved) = (self.fresh("'lab"), self.fresh("v"));
+                let otherwise = [Self::restore(&saved, backward), format!("break {};", label)];
+                let mut inner = Code::default();
+                self.command(command, backward, &otherwise, &mut inner)?;
+                if inner.mentions(&label) {
+                    code.line(format!("let {} = {};", saved, Self::save(backward)));
+                    code.block(format!("{}: {{", label), inner, "}");
+                } else {
+                    code.append(inner);
+                }
+            }
+            Command::Test(command) => {
+                let saved = self.fresh("v");
+                let mut inner = Code::default();
+                let ok = self.command(command, backward, fail, &mut inner)?;
+                if ok {
+                    code.line(format!("let {} = {};", saved, Self::save(backward)));
+                }
+                code.append(inner);
+                if !ok {
+                    return Ok(false);
+                }
+                code.line(Self::restore(&saved, backward));
+            }
+            Command::Do(command) => {
+                let (label, saved) = (self.fresh("'lab"), self.fresh("v"));
+                let mut inner = Code::default();
+                self.command(
+                    command,
+                    backward,
+                    &[format!("break {};", label)],
+                    &mut inner,
+                )?;
+                code.line(format!("let {} = {};", saved, Self::save(backward)));
+                if inner.mentions(&label) {
+                    code.block(format!("{}: {{", label), inner, "}");
+                } else {
+                    code.append(inner);
+                }
+                code.line(Self::restore(&saved, backward));
+            }
+            Command::Fail(command) => {
+                if self.command(command, backward, fail, code)? {
+                    code.lines(fail);
+                }
+                return Ok(false);
+            }
+            Command::Repeat(command) => {
+                return self.repeat(command, None, backward, fail, code);
+            }
+            Command::Atleast(count, command) => {
+                return self.repeat(command, Some(count), backward, fail, code);
+            }
+            Command::Loop(count, command) => {
+                let (count, _) = self.expr(count, backward);
+                let mut inner = Code::default();
+                self.loops += 1;
+                self.command(command, backward, fail, &mut inner)?;
+                self.loops -= 1;
+                code.block(format!("for _ in 0..{} {{", count), inner, "}");
+            }
+            Command::Goto(target) | Command::Gopast(target) => {
+                let (label, saved) = (self.fresh("'lab"), self.fresh("v"));
+                let goto = matches!(command, Command::Goto(_));
+                let mut inner = Code::default();
+                self.loops += 1;
+                let ok =
+                    self.command(target, backward, &[format!("break {};", label)], &mut inner)?;
+                self.loops -= 1;
+                if !inner.mentions(&label) {
+                    // The command cannot fail, so it succeeds where it starts.
+                    if goto && ok {
+                        code.line(format!("let {} = {};", saved, Self::save(backward)));
+                        code.append(inner);
+                        code.line(Self::restore(&saved, backward));
+                    } else {
+                        code.append(inner);
+                    }
+                    return Ok(ok);
+                }
+                let repeat = self.fresh("'lab");
+                if ok {
+                    if goto {
+                        inner.line(Self::restore(&saved, backward));
+                    }
+                    inner.line(format!("break {};", repeat));
+                }
+                let mut body = Code::default();
+                body.line(format!("let {} = {};", saved, Self::save(backward)));
+                body.block(format!("{}: {{", label), inner, "}");
+                body.line(Self::restore(&saved, backward));
+                body.check(format!("!{}", next), fail);
+                code.block(format!("{}: loop {{", repeat), body, "}");
+                return Ok(ok);
+            }
+            Command::Backwards(command) => {
+                code.line("env.limit_backward = env.cursor;");
+                code.line("env.cursor = env.limit;");
+                if !self.command(command, true, fail, code)? {
+                    return Ok(false);
+                }
+                code.line("env.cursor = env.limit_backward;");
+            }
+            Command::Setlimit(limit, command) => {
+                let (saved, limit_saved) = (self.fresh("v"), self.fresh("v"));
+                code.line(format!("let {} = {};", saved, Self::save(backward)));
+                if !self.command(limit, backward, fail, code)? {
+                    return Ok(false);
+                }
+                let restore_limit = if backward {
+                    code.line(format!("let {} = env.limit_backward;", limit_saved));
+                    code.line("env.limit_backward = env.cursor;");
+                    format!("env.limit_backward = {};", limit_saved)
+                } else {
+                    code.line(format!("let {} = env.limit - env.cursor;", limit_saved));
+                    code.line("env.limit = env.cursor;");
+                    format!("env.limit += {};", limit_saved)
+                };
+                code.line(Self::restore(&saved, backward));
+                let mut otherwise = vec![restore_limit.clone()];
+                otherwise.extend_from_slice(fail);
+                if !self.command(command, backward, &otherwise, code)? {
+                    return Ok(false);
+                }
+                code.line(restore_limit);
+            }
+            Command::Hop(count) => {
+                let (count, _) = self.expr(count, backward);
+                let function = if backward { "hop_back" } else { "hop" };
+                code.check(format!("!env.{}({})", function, count), fail);
+            }
+            Command::Tomark(mark) => {
+                let (mark, _) = self.expr(mark, backward);
+                let function = if backward { "to_mark_back" } else { "to_mark" };
+                code.check(format!("!env.{}({})", function, mark), fail);
+            }
+            Command::Atmark(mark) => {
+                let (mark, _) = self.expr(mark, backward);
+                code.check(format!("{} != env.cursor as i32", mark), fail);
+            }
+            Command::Next => code.check(format!("!{}", next), fail),
+            Command::Delete => code.check("!env.slice_del()".to_string(), fail),
+            Command::Tolimit if backward => code.line("env.cursor = env.limit_backward;"),
+            Command::Tolimit => code.line("env.cursor = env.limit;"),
+            Command::Atlimit if backward => {
+                code.check("env.cursor > env.limit_backward".to_string(), fail)
+            }
+            Command::Atlimit => code.check("env.cursor < env.limit".to_string(), fail),
+            Command::True => {}
+            Command::False => {
+                code.lines(fail);
+                return Ok(false);
+            }
+            Command::Bra if backward => code.line("env.ket = env.cursor;"),
+            Command::Bra => code.line("env.bra = env.cursor;"),
+            Command::Ket if backward => code.line("env.bra = env.cursor;"),
+            Command::Ket => code.line("env.ket = env.cursor;"),
+            Command::Substring(index) => {
+                self.find(self.program.substrings[*index], backward, fail, code);
+            }
+            Command::Among(index) => return self.among(*index, backward, fail, code),
+            Command::SliceFrom(text) => {
+                let text = self.text(text);
+                code.check(format!("!env.slice_from({})", text), fail);
+            }
+            Command::Insert(text) | Command::Attach(text) => {
+                let text = self.text(text);
+                // Going backwards, an insertion leaves the cursor in front of
+                // the text, which is behind it.
+                let insert = matches!(command, Command::Insert(_)) != backward;
+                let function = if insert { "insert" } else { "attach" };
+                code.line(format!("env.{}({});", function, text));
+            }
+            Command::SliceTo(name) => {
+                self.variable(name);
+                code.check(format!("!env.slice_to(&mut context.s_{})", name), fail);
+            }
+            Command::Setmark(name) => {
+                self.variable(name);
+                code.line(format!("context.i_{} = env.cursor as i32;", name));
+            }
+            Command::Set(name) | Command::Unset(name) => {
+                self.variable(name);
+                let value = matches!(command, Command::Set(_));
+                code.line(format!("context.b_{} = {};", name, value));
+            }
+            Command::Literal(s) => code.check(format!("!env.eq_s{}({:?})", suffix, s), fail),
+            Command::StringVariable(name) => {
+                self.variable(name);
+                code.check(format!("!env.eq_s{}(&context.s_{})", suffix, name), fail);
+            }
+            Command::InGrouping(name) | Command::OutGrouping(name) => {
+                self.groupings.insert(name);
+                let kind = if matches!(command, Command::InGrouping(_)) {
+                    "in"
+                } else {
+                    "out"
+                };
+                code.check(
+                    format!("!env.{}_grouping{}(g_{})", kind, suffix, name),
+                    fail,
+                );
+            }
+            Command::Call(name, line) => {
+                self.check_mode(name, backward, *line)?;
+                code.check(format!("!r_{}(env, context)", name), fail);
+            }
+            Command::Boolean(name) => {
+                self.variable(name);
+                code.check(format!("!context.b_{}", name), fail);
+            }
+            Command::Assign(name, operator, value) => {
+                self.variable(name);
+                let (value, _) = self.expr(value, backward);
+                code.line(format!("context.i_{} {} {};", name, operator, value));
+            }
+            Command::Compare(left, comparison, right) => {
+                let comparison = negate(comparison);
+                let left = self.expr(left, backward);
+                // `x as i32 < y` would read as the start of generic arguments.
+                let left = parenthesize(left, if comparison.starts_with('<') { 4 } else { 0 });
+                let (right, _) = self.expr(right, backward);
+                code.check(format!("{} {} {}", left, comparison, right), fail);
+            }
+        }
+        Ok(true)
+    }
+
+    fn or(
+        &mut self,
+        commands: &'a [Command],
+        backward: bool,
+        fail: &[String],
+        code: &mut Code,
+    ) -> Result<bool, CompileError> {
+        let (label, saved) = (self.fresh("'lab"), self.fresh("v"));
+        let mut body = Code::default();
+        let mut succeeds = false;
+        for (i, command) in commands.iter().enumerate() {
+            if i + 1 == commands.len() {
+                succeeds |= self.command(command, backward, fail, &mut body)?;
+                break;
+            }
+            let alternative = self.fresh("'lab");
+            let mut inner = Code::default();
+            let otherwise = [format!("break {};", alternative)];
+            let ok = self.command(command, backward, &otherwise, &mut inner)?;
+            if !inner.mentions(&alternative) {
+                // The alternatives after one that cannot fail are never tried.
+                body.append(inner);
+                succeeds |= ok;
+                break;
+            }
+            if ok {
+                inner.line(format!("break {};", label));
+                succeeds = true;
+            }
+            body.block(format!("{}: {{", alternative), inner, "}");
+            body.line(Self::restore(&saved, backward));
+        }
+        if body.mentions(&saved) {
+            code.line(format!("let {} = {};", saved, Self::save(backward)));
+        }
+        if body.mentions(&label) {
+            code.block(format!("{}: {{", label), body, "}");
+        } else {
+            code.append(body);
+        }
+        Ok(succeeds)
+    }
+
+    // `repeat`, or `atleast` with its count.
+    fn repeat(
+        &mut self,
+        command: &'a Command,
+        count: Option<&'a Expr>,
+        backward: bool,
+        fail: &[String],
+        code: &mut Code,
+    ) -> Result<bool, CompileError> {
+        let (label, saved, counter) = (self.fresh("'lab"), self.fresh("v"), self.fresh("i"));
+        let count = count.map(|count| self.expr(count, backward).0);
+        let mut inner = Code::default();
+        self.loops += 1;
+        let ok = self.command(
+            command,
+            backward,
+            &[format!("break {};", label)],
+            &mut inner,
+        )?;
+        self.loops -= 1;
+        if !inner.mentions(&label) {
+            // The command never fails, so the loop never ends.
+            code.block("loop {".to_string(), inner, "}");
+            return Ok(false);
+        }
+        if !ok {
+            // The command always fails, so it runs once.
+            code.line(format!("let {} = {};", saved, Self::save(backward)));
+            code.block(format!("{}: {{", label), inner, "}");
+            code.line(Self::restore(&saved, backward));
+            if let Some(count) = count {
+                code.check(format!("{} > 0", count), fail);
+            }
+            return Ok(true);
+        }
+        let repeat = self.fresh("'lab");
+        if count.is_some() {
+            inner.line(format!("{} -= 1;", counter));
+        }
+        inner.line(format!("continue {};", repeat));
+        let mut body = Code::default();
+        body.line(format!("let {} = {};", saved, Self::save(backward)));
+        body.block(format!("{}: {{", label), inner, "}");
+        body.line(Self::restore(&saved, backward));
+        body.line(format!("break {};", repeat));
+        if let Some(count) = &count {
+            code.line(format!("let mut {} = {};", counter, count));
+        }
+        code.block(format!("{}: loop {{", repeat), body, "}");
+        if count.is_some() {
+            code.check(format!("{} > 0", counter), fail);
+        }
+        Ok(true)
+    }
+
+    // An among, finding its string first unless a substring has.
+    fn among(
+        &mut self,
+        index: usize,
+        backward: bool,
+        fail: &[String],
+        code: &mut Code,
+    ) -> Result<bool, CompileError> {
+        let among = &self.program.amongs[index];
+        if !among.has_substring {
+            self.find(index, backward, fail, code);
+        }
+        if let Some(starter) = &among.starter {
+            if !self.command(starter, backward, fail, code)? {
+                return Ok(false);
+            }
+        }
+        if !self.dispatches(among) {
+            return match among.commands.first() {
+                Some(command) => self.command(command, backward, fail, code),
+                None => Ok(true),
+            };
+        }
+        let mut arms = Code::default();
+        let mut succeeds = among
+            .strings
+            .iter()
+            .any(|s| s.result > among.commands.len());
+        for (i, command) in among.commands.iter().enumerate() {
+            let mut arm = Code::default();
+            succeeds |= self.command(command, backward, fail, &mut arm)?;
+            arms.block(format!("{} => {{", i + 1), arm, "}");
+        }
+        arms.line("_ => {}");
+        code.block("match among_var {".to_string(), arms, "}");
+        Ok(succeeds)
+    }
+}
@@ -0,0 +1,285 @@
+// Splitting a Snowball source into tokens. `stringescapes` and `stringdef`
+// are dealt with here, as Snowball's own compiler does, so the parser sees
+// string literals with their escapes already replaced.
+
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::CompileError;
+
+const SYMBOLS: &[&str] = &[
+    "<-", "->", "<+", "+=", "-=", "*=", "/=", "==", "!=", ">=", "<=", "(", ")", "[", "]", "$", "=",
+    "<", ">", "+", "-", "*", "/", "?",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Token {
+    // A keyword or a name.
+    Name(String),
+    Number(i32),
+    Literal(String),
+    Symbol(&'static str),
+}
+
+// A token with the line it is on.
+pub(super) type Spanned = (Token, usize);
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    escapes: Option<(char, char)>,
+    stringdefs: HashMap<String, String>,
+}
+
+pub(super) fn tokenize(source: &str) -> Result<Vec<Spanned>, CompileError> {
+    let mut lexer = Lexer {
+        chars: source.chars().peekable(),
+        line: 1,
+        escapes: None,
+        stringdefs: HashMap::new(),
+    };
+    let mut tokens = Vec::new();
+    while let Some((token, line)) = lexer.token()? {
+        match token {
+            Token::Name(ref name) if name == "stringescapes" => {
+                let open = lexer.char_after_space()?;
+                let close = lexer.char_after_space()?;
+                lexer.escapes = Some((open, close));
+            }
+            Token::Name(ref name) if name == "stringdef" => lexer.stringdef()?,
+            token => tokens.push((token, line)),
+        }
+    }
+    Ok(tokens)
+}
+
+impl<'a> Lexer<'a> {
+    fn error<T>(&self, message: String) -> Result<T, CompileError> {
+        Err(CompileError::new(self.line, message))
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn skip_space(&mut self) -> Result<(), CompileError> {
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.next_char();
+                }
+                Some('/') => {
+                    let mut ahead = self.chars.clone();
+                    ahead.next();
+                    match ahead.next() {
+                        Some('/') => while !matches!(self.next_char(), Some('\n') | None) {},
+                        Some('*') => {
+                            self.next_char();
+                            self.next_char();
+                            let mut previous = ' ';
+                            loop {
+                                match self.next_char() {
+                                    Some('/') if previous == '*' => break,
+                                    Some(c) => previous = c,
+                                    None => return self.error("unterminated comment".to_string()),
+                                }
+                            }
+                        }
+                        _ => return Ok(()),
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn char_after_space(&mut self) -> Result<char, CompileError> {
+        self.skip_space()?;
+        match self.next_char() {
+            Some(c) => Ok(c),
+            None => self.error("unexpected end of input".to_string()),
+        }
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, f: F) -> String {
+        let mut taken = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !f(c) {
+                break;
+            }
+            taken.push(c);
+            self.next_char();
+        }
+        taken
+    }
+
+    fn token(&mut self) -> Result<Option<Spanned>, CompileError> {
+        self.skip_space()?;
+        let line = self.line;
+        Ok(self.token_at()?.map(|token| (token, line)))
+    }
+
+    fn token_at(&mut self) -> Result<Option<Token>, CompileError> {
+        let c = match self.chars.peek() {
+            Some(&c) => c,
+            None => return Ok(None),
+        };
+        if c.is_ascii_alphabetic() || c == '_' {
+            let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            if name == "hex" || name == "decimal" {
+                self.skip_space()?;
+                if self.chars.peek() == Some(&'\'') {
+                    let digits = self.literal()?;
+                    return self.number_literal(&digits, &name).map(Some);
+                }
+            }
+            return Ok(Some(Token::Name(name)));
+        }
+        if c.is_ascii_digit() {
+            let digits = self.take_while(|c| c.is_ascii_digit());
+            return match digits.parse() {
+                Ok(n) => Ok(Some(Token::Number(n))),
+                Err(_) => self.error(format!("{} is too large", digits)),
+            };
+        }
+        if c == '\'' {
+            return self.literal().map(|s| Some(Token::Literal(s)));
+        }
+        let mut ahead = self.chars.clone();
+        let two: String = (&mut ahead).take(2).collect();
+        for symbol in SYMBOLS {
+            if two.starts_with(symbol) {
+                for _ in 0..symbol.len() {
+                    self.next_char();
+                }
+                return Ok(Some(Token::Symbol(symbol)));
+            }
+        }
+        self.error(format!("unexpected character {:?}", c))
+    }
+
+    // A string literal, from its opening quote, with its escapes replaced.
+    fn literal(&mut self) -> Result<String, CompileError> {
+        self.next_char();
+        let mut s = String::new();
+        loop {
+            match self.next_char() {
+                Some('\'') => return Ok(s),
+                Some(c) if self.escapes.map(|(open, _)| open) == Some(c) => {
+                    let close = self.escapes.map(|(_, close)| close).unwrap_or(c);
+                    let mut name = String::new();
+                    loop {
+                        match self.next_char() {
+                            Some(c) if c == close => break,
+                            Some(c) => name.push(c),
+                            None => return self.error("unterminated string".to_string()),
+                        }
+                    }
+                    s.push_str(&self.escape(&name, c)?);
+                }
+                Some(c) => s.push(c),
+                None => return self.error("unterminated string".to_string()),
+            }
+        }
+    }
+
+    // The text of `{name}`. A stringdef can redefine `{'}` and `{{}`.
+    fn escape(&self, name: &str, open: char) -> Result<String, CompileError> {
+        if let Some(s) = self.stringdefs.get(name) {
+            return Ok(s.clone());
+        }
+        if name == "'" || name == open.to_string() {
+            return Ok(name.to_string());
+        }
+        match name.strip_prefix("U+") {
+            Some(hex) => match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+                Some(c) => Ok(c.to_string()),
+                None => self.error(format!("{} is not a character", name)),
+            },
+            None => self.error(format!("unknown stringdef {}", name)),
+        }
+    }
+
+    // The characters of a `hex` or `decimal` literal: code points separated
+    // by spaces.
+    fn number_literal(&self, digits: &str, base: &str) -> Result<Token, CompileError> {
+        let radix = if base == "hex" { 16 } else { 10 };
+        let mut s = String::new();
+        for number in digits.split_whitespace() {
+            match u32::from_str_radix(number, radix)
+                .ok()
+                .and_then(char::from_u32)
+            {
+                Some(c) => s.push(c),
+                None => return self.error(format!("{} is not a {} character", number, base)),
+            }
+        }
+        Ok(Token::Literal(s))
+    }
+
+    // `stringdef NAME STRING`, once `stringdef` has been read.
+    fn stringdef(&mut self) -> Result<(), CompileError> {
+        self.skip_space()?;
+        let name = self.take_while(|c| !c.is_whitespace());
+        if name.is_empty() {
+            return self.error("stringdef without a name".to_string());
+        }
+        match self.token()? {
+            Some((Token::Literal(s), _)) => {
+                self.stringdefs.insert(name, s);
+                Ok(())
+            }
+            _ => self.error(format!("expected a string for stringdef {}", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let source = "
+            stringescapes {}
+            stringdef a\" hex 'E4'
+            stringdef e' decimal '233 101'
+            /* a comment */ define v 'a{a\"}{e'}{'}{{}{U+00FC}' // another
+            $p1 <= cursor non-v
+        ";
+        assert_eq!(
+            tokens(source),
+            [
+                Token::Name("define".to_string()),
+                Token::Name("v".to_string()),
+                Token::Literal("a\u{e4}\u{e9}e'{\u{fc}".to_string()),
+                Token::Symbol("$"),
+                Token::Name("p1".to_string()),
+                Token::Symbol("<="),
+                Token::Name("cursor".to_string()),
+                Token::Name("non".to_string()),
+                Token::Symbol("-"),
+                Token::Name("v".to_string()),
+            ]
+        );
+        let lines: Vec<usize> = tokenize("a\n/*\n*/ b\n'\n'")
+            .unwrap()
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect();
+        assert_eq!(lines, [1, 3, 4]);
+    }
+}
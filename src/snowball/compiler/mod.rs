@@ -0,0 +1,116 @@
+// A compiler from Snowball to Rust.
+//
+// The source is split into tokens, parsed into a program of declarations and
+// routine definitions, and written out as a Rust module body that runs on
+// `stemmer::snowball::runtime`. Each external routine becomes a
+// `pub fn NAME(env: &mut Env) -> bool`; the other routines, the groupings and
+// the `among` tables are private to the module.
+//
+// The build script compiles the sources in `snowball/` with these files as a
+// module of its own, so nothing here may refer to the rest of the crate.
+
+use std::error;
+use std::fmt;
+
+mod generator;
+mod lexer;
+mod parser;
+
+/// An error in a Snowball source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    line: usize,
+    message: String,
+}
+
+impl CompileError {
+    fn new(line: usize, message: String) -> CompileError {
+        CompileError { line, message }
+    }
+
+    /// The line of the error, counting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl error::Error for CompileError {}
+
+/// Compiles the Snowball program `source` to the body of a Rust module.
+///
+/// The module needs `stemmer::snowball::runtime`, and has a
+/// `pub fn NAME(env: &mut Env) -> bool` for each of the program's externals.
+/// `Snowball::with_external` makes a stemmer of one.
+pub fn compile(source: &str) -> Result<String, CompileError> {
+    let tokens = lexer::tokenize(source)?;
+    let program = parser::parse(tokens)?;
+    generator::generate(&program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> (usize, String) {
+        let error = compile(source).unwrap_err();
+        (error.line(), error.message().to_string())
+    }
+
+    #[test]
+    fn test_compile() {
+        let source = "
+            routines (ending)
+            externals (stem)
+            groupings (v)
+            define v 'aeiou'
+            backwardmode (
+                define ending as ([substring] among ('s' 'es' (delete) 'ss'))
+            )
+            define stem as (gopast v backwards do ending)
+        ";
+        let rust = compile(source).unwrap();
+        assert!(rust.contains("pub fn stem(env: &mut Env) -> bool {"));
+        assert!(rust.contains("fn r_ending(env: &mut Env, context: &mut Context) -> bool {"));
+        assert!(rust.contains("matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            error("externals (stem)\n\ndefine stem as (hop x)"),
+            (3, "x is not declared".to_string())
+        );
+        assert_eq!(
+            error("externals (stem)\ndefine stem as among ('a' 'b'\n'a')"),
+            (3, "'a' appears twice in the among".to_string())
+        );
+        assert_eq!(
+            error("stringescapes {}\nexternals (stem)\ndefine stem as '{e\"}'"),
+            (3, "unknown stringdef e\"".to_string())
+        );
+        assert_eq!(
+            error(
+                "routines (r)\nexternals (stem)\nbackwardmode (define r as true)\ndefine stem as r"
+            ),
+            (4, "r is a backward routine called forwards".to_string())
+        );
+        assert_eq!(
+            error("externals (stem)\ndefine stem as (true"),
+            (2, "expected ), found end of input".to_string())
+        );
+        assert_eq!(
+            error("routines (r)\nexternals (stem)\ndefine stem as r"),
+            (1, "routine r is not defined".to_string())
+        );
+    }
+}
@@ -0,0 +1,740 @@
+// Parsing the tokens of a Snowball source into a program.
+//
+// Commands in a list run one after another. Inside a list, `and` and `or`
+// join the commands either side of them, left to right and with the same
+// precedence, so `(a b or c and d)` is `a ((b or c) and d)`. The prefix
+// operators (`not`, `try`, `repeat` and so on) take the one command after
+// them. A `substring` goes with the next `among` of its routine.
+
+use std::collections::HashMap;
+
+use super::lexer::{Spanned, Token};
+use super::CompileError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Kind {
+    String,
+    Integer,
+    Boolean,
+    Routine,
+    External,
+    Grouping,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Declaration {
+    pub name: String,
+    pub kind: Kind,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Routine {
+    pub name: String,
+    pub backward: bool,
+    pub body: Command,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Grouping {
+    pub name: String,
+    // The characters of the grouping, sorted.
+    pub chars: Vec<char>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct AmongString {
+    pub string: String,
+    // A routine that must succeed for the string to match.
+    pub condition: Option<String>,
+    // Which command the string selects, counting from 1. Strings after the
+    // last command select none.
+    pub result: usize,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Among {
+    pub strings: Vec<AmongString>,
+    // A command run after a string matches, before the string's own.
+    pub starter: Option<Command>,
+    pub commands: Vec<Command>,
+    // Whether a `substring` has already found the string.
+    pub has_substring: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Program {
+    pub declarations: Vec<Declaration>,
+    pub routines: Vec<Routine>,
+    pub groupings: Vec<Grouping>,
+    pub amongs: Vec<Among>,
+    // The among that each `substring` goes with.
+    pub substrings: Vec<usize>,
+}
+
+// A string in an insertion or replacement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Text {
+    Literal(String),
+    Variable(String),
+}
+
+// An arithmetic expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Expr {
+    Number(i32),
+    Variable(String),
+    Cursor,
+    Limit,
+    Size,
+    Len,
+    Sizeof(String),
+    Lenof(String),
+    Maxint,
+    Minint,
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, &'static str, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Command {
+    List(Vec<Command>),
+    Or(Vec<Command>),
+    And(Vec<Command>),
+    Not(Box<Command>),
+    Test(Box<Command>),
+    Try(Box<Command>),
+    Do(Box<Command>),
+    Fail(Box<Command>),
+    Repeat(Box<Command>),
+    Backwards(Box<Command>),
+    Goto(Box<Command>),
+    Gopast(Box<Command>),
+    Loop(Expr, Box<Command>),
+    Atleast(Expr, Box<Command>),
+    Setlimit(Box<Command>, Box<Command>),
+    Hop(Expr),
+    Tomark(Expr),
+    Atmark(Expr),
+    Next,
+    Delete,
+    Tolimit,
+    Atlimit,
+    True,
+    False,
+    Bra,
+    Ket,
+    // The index of a `substring` in `Program::substrings`.
+    Substring(usize),
+    // The index of an `among` in `Program::amongs`.
+    Among(usize),
+    SliceFrom(Text),
+    Insert(Text),
+    Attach(Text),
+    SliceTo(String),
+    Setmark(String),
+    Set(String),
+    Unset(String),
+    Literal(String),
+    StringVariable(String),
+    InGrouping(String),
+    OutGrouping(String),
+    // A routine call, with its line for errors about it.
+    Call(String, usize),
+    Boolean(String),
+    Assign(String, &'static str, Expr),
+    Compare(Expr, &'static str, Expr),
+}
+
+const DECLARATIONS: &[(&str, Kind)] = &[
+    ("strings", Kind::String),
+    ("integers", Kind::Integer),
+    ("booleans", Kind::Boolean),
+    ("routines", Kind::Routine),
+    ("externals", Kind::External),
+    ("groupings", Kind::Grouping),
+];
+
+const PREFIXES: &[&str] = &[
+    "not",
+    "test",
+    "try",
+    "do",
+    "fail",
+    "repeat",
+    "backwards",
+    "goto",
+    "gopast",
+];
+
+const ASSIGNMENTS: &[&str] = &["=", "+=", "-=", "*=", "/="];
+
+const COMPARISONS: &[&str] = &["==", "!=", ">", ">=", "<", "<="];
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    position: usize,
+    kinds: HashMap<String, Kind>,
+    backward: bool,
+    // A `substring` of the current routine still waiting for its `among`.
+    substring: Option<usize>,
+    program: Program,
+}
+
+pub(super) fn parse(tokens: Vec<Spanned>) -> Result<Program, CompileError> {
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        kinds: HashMap::new(),
+        backward: false,
+        substring: None,
+        program: Program {
+            declarations: Vec::new(),
+            routines: Vec::new(),
+            groupings: Vec::new(),
+            amongs: Vec::new(),
+            substrings: Vec::new(),
+        },
+    };
+    while parser.peek().is_some() {
+        parser.item()?;
+    }
+    for declaration in &parser.program.declarations {
+        let defined = match declaration.kind {
+            Kind::Routine | Kind::External => {
+                let routines = &parser.program.routines;
+                routines
+                    .iter()
+                    .any(|routine| routine.name == declaration.name)
+            }
+            Kind::Grouping => {
+                let groupings = &parser.program.groupings;
+                groupings
+                    .iter()
+                    .any(|grouping| grouping.name == declaration.name)
+            }
+            _ => true,
+        };
+        if !defined {
+            let kind = format!("{:?}", declaration.kind).to_lowercase();
+            let message = format!("{} {} is not defined", kind, declaration.name);
+            return Err(CompileError::new(declaration.line, message));
+        }
+    }
+    Ok(parser.program)
+}
+
+fn describe(token: Option<&Token>) -> String {
+    match token {
+        Some(Token::Name(name)) => name.clone(),
+        Some(Token::Number(n)) => n.to_string(),
+        Some(Token::Literal(s)) => format!("'{}'", s),
+        Some(Token::Symbol(symbol)) => symbol.to_string(),
+        None => "end of input".to_string(),
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        match self
+            .tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+        {
+            Some(&(_, line)) => line,
+            None => 1,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, CompileError> {
+        Err(CompileError::new(self.line(), message))
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T, CompileError> {
+        self.error(format!(
+            "expected {}, found {}",
+            expected,
+            describe(self.peek())
+        ))
+    }
+
+    // Reads the symbol if it comes next.
+    fn symbol(&mut self, symbol: &str) -> bool {
+        match self.peek() {
+            Some(Token::Symbol(s)) if *s == symbol => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), CompileError> {
+        if self.symbol(symbol) {
+            Ok(())
+        } else {
+            self.unexpected(symbol)
+        }
+    }
+
+    // Reads the keyword if it comes next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Name(name)) if name == keyword => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // A declared name of one of `kinds`.
+    fn name(&mut self, kinds: &[Kind]) -> Result<String, CompileError> {
+        let name = match self.peek() {
+            Some(Token::Name(name)) => name.clone(),
+            _ => return self.unexpected("a name"),
+        };
+        match self.kinds.get(&name) {
+            Some(kind) if kinds.contains(kind) => {
+                self.position += 1;
+                Ok(name)
+            }
+            Some(kind) => self.error(format!(
+                "{} is a {}",
+                name,
+                format!("{:?}", kind).to_lowercase()
+            )),
+            None => self.error(format!("{} is not declared", name)),
+        }
+    }
+
+    fn item(&mut self) -> Result<(), CompileError> {
+        let line = self.line();
+        let word = match self.next() {
+            Some(Token::Name(word)) => word,
+            token => {
+                self.position -= 1;
+                return self.error(format!("unexpected {}", describe(token.as_ref())));
+            }
+        };
+        if let Some(&(_, kind)) = DECLARATIONS.iter().find(|(keyword, _)| *keyword == word) {
+            self.expect("(")?;
+            while !self.symbol(")") {
+                let line = self.line();
+                match self.next() {
+                    Some(Token::Name(name)) => {
+                        if self.kinds.insert(name.clone(), kind).is_some() {
+                            return self.error(format!("{} is declared twice", name));
+                        }
+                        self.program
+                            .declarations
+                            .push(Declaration { name, kind, line });
+                    }
+                    _ => {
+                        self.position -= 1;
+                        return self.unexpected("a name or )");
+                    }
+                }
+            }
+            return Ok(());
+        }
+        match word.as_str() {
+            "define" => self.define(),
+            "backwardmode" => {
+                self.expect("(")?;
+                self.backward = true;
+                while !self.symbol(")") {
+                    if self.peek().is_none() {
+                        return self.unexpected(")");
+                    }
+                    self.item()?;
+                }
+                self.backward = false;
+                Ok(())
+            }
+            _ => Err(CompileError::new(line, format!("unexpected {}", word))),
+        }
+    }
+
+    fn define(&mut self) -> Result<(), CompileError> {
+        let name = self.name(&[Kind::Routine, Kind::External, Kind::Grouping])?;
+        if self.kinds[&name] == Kind::Grouping {
+            if self.program.groupings.iter().any(|g| g.name == name) {
+                return self.error(format!("{} is defined twice", name));
+            }
+            let chars = self.grouping()?;
+            self.program.groupings.push(Grouping { name, chars });
+            return Ok(());
+        }
+        if self.program.routines.iter().any(|r| r.name == name) {
+            return self.error(format!("{} is defined twice", name));
+        }
+        if !self.keyword("as") {
+            return self.unexpected("as");
+        }
+        self.substring = None;
+        let body = self.command()?;
+        if self.substring.is_some() {
+            return self.error(format!("substring without an among in {}", name));
+        }
+        self.program.routines.push(Routine {
+            name,
+            backward: self.backward,
+            body,
+        });
+        Ok(())
+    }
+
+    // The characters of a grouping: strings and groupings joined with + and -.
+    fn grouping(&mut self) -> Result<Vec<char>, CompileError> {
+        let mut chars = Vec::new();
+        let mut add = true;
+        loop {
+            let operand = match self.peek() {
+                Some(Token::Literal(s)) => {
+                    let operand = s.chars().collect();
+                    self.position += 1;
+                    operand
+                }
+                _ => {
+                    let name = self.name(&[Kind::Grouping])?;
+                    match self.program.groupings.iter().find(|g| g.name == name) {
+                        Some(grouping) => grouping.chars.clone(),
+                        None => {
+                            self.position -= 1;
+                            return self.error(format!("{} is used before it is defined", name));
+                        }
+                    }
+                }
+            };
+            if add {
+                chars.extend(operand);
+            } else {
+                chars.retain(|c| !operand.contains(c));
+            }
+            if self.symbol("+") {
+                add = true;
+            } else if self.symbol("-") {
+                add = false;
+            } else {
+                break;
+            }
+        }
+        chars.sort_unstable();
+        chars.dedup();
+        Ok(chars)
+    }
+
+    // The commands of a list, once its ( has been read.
+    fn list(&mut self) -> Result<Vec<Command>, CompileError> {
+        let mut commands = Vec::new();
+        while !self.symbol(")") {
+            if self.peek().is_none() {
+                return self.unexpected(")");
+            }
+            let mut command = self.command()?;
+            loop {
+                if self.keyword("or") {
+                    let right = self.command()?;
+                    command = match command {
+                        Command::Or(mut commands) => {
+                            commands.push(right);
+                            Command::Or(commands)
+                        }
+                        left => Command::Or(vec![left, right]),
+                    };
+                } else if self.keyword("and") {
+                    let right = self.command()?;
+                    command = match command {
+                        Command::And(mut commands) => {
+                            commands.push(right);
+                            Command::And(commands)
+                        }
+                        left => Command::And(vec![left, right]),
+                    };
+                } else {
+                    break;
+                }
+            }
+            commands.push(command);
+        }
+        Ok(commands)
+    }
+
+    fn command(&mut self) -> Result<Command, CompileError> {
+        let line = self.line();
+        let token = match self.next() {
+            Some(token) => token,
+            None => return self.unexpected("a command"),
+        };
+        let word = match token {
+            Token::Symbol("(") => return Ok(Command::List(self.list()?)),
+            Token::Symbol("[") => return Ok(Command::Bra),
+            Token::Symbol("]") => return Ok(Command::Ket),
+            Token::Symbol("?") => return Ok(Command::True),
+            Token::Symbol("<-") => return Ok(Command::SliceFrom(self.text()?)),
+            Token::Symbol("<+") => return Ok(Command::Insert(self.text()?)),
+            Token::Symbol("->") => return Ok(Command::SliceTo(self.name(&[Kind::String])?)),
+            Token::Symbol("$") => return self.integer_command(),
+            Token::Literal(s) => return Ok(Command::Literal(s)),
+            Token::Name(word) => word,
+            token => {
+                self.position -= 1;
+                return self.error(format!("unexpected {}", describe(Some(&token))));
+            }
+        };
+        if PREFIXES.contains(&word.as_str()) {
+            let command = Box::new(self.command()?);
+            return Ok(match word.as_str() {
+                "not" => Command::Not(command),
+                "test" => Command::Test(command),
+                "try" => Command::Try(command),
+                "do" => Command::Do(command),
+                "fail" => Command::Fail(command),
+                "repeat" => Command::Repeat(command),
+                "backwards" => Command::Backwards(command),
+                "goto" => Command::Goto(command),
+                _ => Command::Gopast(command),
+            });
+        }
+        Ok(match word.as_str() {
+            "loop" => Command::Loop(self.expr()?, Box::new(self.command()?)),
+            "atleast" => Command::Atleast(self.expr()?, Box::new(self.command()?)),
+            "setlimit" => {
+                let limit = self.command()?;
+                if !self.keyword("for") {
+                    return self.unexpected("for");
+                }
+                Command::Setlimit(Box::new(limit), Box::new(self.command()?))
+            }
+            "hop" => Command::Hop(self.expr()?),
+            "tomark" => Command::Tomark(self.expr()?),
+            "atmark" => Command::Atmark(self.expr()?),
+            "next" => Command::Next,
+            "delete" => Command::Delete,
+            "tolimit" => Command::Tolimit,
+            "atlimit" => Command::Atlimit,
+            "true" => Command::True,
+            "false" => Command::False,
+            "insert" => Command::Insert(self.text()?),
+            "attach" => Command::Attach(self.text()?),
+            "setmark" => Command::Setmark(self.name(&[Kind::Integer])?),
+            "set" => Command::Set(self.name(&[Kind::Boolean])?),
+            "unset" => Command::Unset(self.name(&[Kind::Boolean])?),
+            "non" => {
+                self.symbol("-");
+                Command::OutGrouping(self.name(&[Kind::Grouping])?)
+            }
+            "substring" => {
+                if self.substring.is_some() {
+                    return self.error("substring without an among".to_string());
+                }
+                let index = self.program.substrings.len();
+                self.program.substrings.push(0);
+                self.substring = Some(index);
+                Command::Substring(index)
+            }
+            "among" => self.among()?,
+            _ => match self.kinds.get(&word) {
+                Some(Kind::Routine) | Some(Kind::External) => Command::Call(word, line),
+                Some(Kind::Grouping) => Command::InGrouping(word),
+                Some(Kind::Boolean) => Command::Boolean(word),
+                Some(Kind::String) => Command::StringVariable(word),
+                Some(Kind::Integer) => {
+                    self.position -= 1;
+                    return self.error(format!("{} is an integer", word));
+                }
+                None => {
+                    self.position -= 1;
+                    return self.error(format!("{} is not declared", word));
+                }
+            },
+        })
+    }
+
+    // A string in an insertion or replacement: a literal or a string variable.
+    fn text(&mut self) -> Result<Text, CompileError> {
+        match self.peek() {
+            Some(Token::Literal(s)) => {
+                let text = Text::Literal(s.clone());
+                self.position += 1;
+                Ok(text)
+            }
+            _ => Ok(Text::Variable(self.name(&[Kind::String])?)),
+        }
+    }
+
+    // A command on integers, once its $ has been read.
+    fn integer_command(&mut self) -> Result<Command, CompileError> {
+        if self.symbol("(") {
+            let left = self.expr()?;
+            let comparison = self.operator(COMPARISONS, "a comparison")?;
+            let right = self.expr()?;
+            self.expect(")")?;
+            return Ok(Command::Compare(left, comparison, right));
+        }
+        if let Some(Token::Name(name)) = self.peek() {
+            if self.kinds.get(name) == Some(&Kind::String) {
+                return self.error(format!("${} is not supported", name));
+            }
+        }
+        let name = self.name(&[Kind::Integer])?;
+        let operator = self.operator(&[ASSIGNMENTS, COMPARISONS].concat(), "= or a comparison")?;
+        let expr = self.expr()?;
+        Ok(if ASSIGNMENTS.contains(&operator) {
+            Command::Assign(name, operator, expr)
+        } else {
+            Command::Compare(Expr::Variable(name), operator, expr)
+        })
+    }
+
+    fn operator(
+        &mut self,
+        operators: &[&str],
+        expected: &str,
+    ) -> Result<&'static str, CompileError> {
+        match self.peek() {
+            Some(&Token::Symbol(symbol)) if operators.contains(&symbol) => {
+                self.position += 1;
+                Ok(symbol)
+            }
+            _ => self.unexpected(expected),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, CompileError> {
+        let mut expr = self.term()?;
+        while let Some(&Token::Symbol(operator @ "+")) | Some(&Token::Symbol(operator @ "-")) =
+            self.peek()
+        {
+            self.position += 1;
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, CompileError> {
+        let mut expr = self.factor()?;
+        while let Some(&Token::Symbol(operator @ "*")) | Some(&Token::Symbol(operator @ "/")) =
+            self.peek()
+        {
+            self.position += 1;
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr, CompileError> {
+        let token = self.peek().cloned();
+        Ok(match token {
+            Some(Token::Symbol("-")) => {
+                self.position += 1;
+                Expr::Negate(Box::new(self.factor()?))
+            }
+            Some(Token::Symbol("(")) => {
+                self.position += 1;
+                let expr = self.expr()?;
+                self.expect(")")?;
+                expr
+            }
+            Some(Token::Number(n)) => {
+                self.position += 1;
+                Expr::Number(n)
+            }
+            Some(Token::Name(word)) => {
+                self.position += 1;
+                match word.as_str() {
+                    "cursor" => Expr::Cursor,
+                    "limit" => Expr::Limit,
+                    "size" => Expr::Size,
+                    "len" => Expr::Len,
+                    "maxint" => Expr::Maxint,
+                    "minint" => Expr::Minint,
+                    "sizeof" => Expr::Sizeof(self.name(&[Kind::String])?),
+                    "lenof" => Expr::Lenof(self.name(&[Kind::String])?),
+                    _ => {
+                        self.position -= 1;
+                        Expr::Variable(self.name(&[Kind::Integer])?)
+                    }
+                }
+            }
+            _ => return self.unexpected("a number or an integer"),
+        })
+    }
+
+    // An among, once `among` has been read.
+    fn among(&mut self) -> Result<Command, CompileError> {
+        self.expect("(")?;
+        // The commands of the among may have substrings of their own.
+        let substring = self.substring.take();
+        let mut among = Among {
+            strings: Vec::new(),
+            starter: None,
+            commands: Vec::new(),
+            has_substring: substring.is_some(),
+        };
+        loop {
+            let line = self.line();
+            match self.next() {
+                Some(Token::Literal(string)) => {
+                    let condition = match self.peek() {
+                        Some(Token::Name(name))
+                            if matches!(
+                                self.kinds.get(name),
+                                Some(Kind::Routine) | Some(Kind::External)
+                            ) =>
+                        {
+                            Some(self.name(&[Kind::Routine, Kind::External])?)
+                        }
+                        _ => None,
+                    };
+                    if among.strings.iter().any(|s| s.string == string) {
+                        return Err(CompileError::new(
+                            line,
+                            format!("'{}' appears twice in the among", string),
+                        ));
+                    }
+                    among.strings.push(AmongString {
+                        string,
+                        condition,
+                        result: among.commands.len() + 1,
+                        line,
+                    });
+                }
+                Some(Token::Symbol("(")) => {
+                    let command = Command::List(self.list()?);
+                    if among.strings.is_empty() && among.commands.is_empty() {
+                        among.starter = Some(command);
+                    } else {
+                        among.commands.push(command);
+                    }
+                }
+                Some(Token::Symbol(")")) => break,
+                _ => {
+                    self.position -= 1;
+                    return self.unexpected("a string, a command or )");
+                }
+            }
+        }
+        if among.strings.is_empty() {
+            return self.error("among without strings".to_string());
+        }
+        let index = self.program.amongs.len();
+        if let Some(substring) = substring {
+            self.program.substrings[substring] = index;
+        }
+        self.program.amongs.push(among);
+        Ok(Command::Among(index))
+    }
+}
@@ -0,0 +1,115 @@
+// Stemmers written in Snowball, the string-processing language in which
+// Porter published Porter2 and the stemmers of other European languages.
+//
+// The build script compiles every `snowball/LANGUAGE.sbl` with `compile` into
+// a module of its own, and `Snowball::new` finds the `stem` external of one.
+// A crate with stemmers of its own can do the same from its build script:
+// write the output of `compile` to a file, `include!` it in a module, and
+// hand one of its externals to `Snowball::with_external`.
+
+use std::borrow::Cow;
+
+use crate::{borrow_prefix, validate_unicode, Stem, StemError};
+
+mod compiler;
+pub mod runtime;
+
+pub use compiler::{compile, CompileError};
+
+use runtime::Env;
+
+#[allow(clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/snowball.rs"));
+}
+
+pub use generated::LANGUAGES;
+
+/// A stemmer compiled from a Snowball program.
+///
+/// Unlike the other stemmers of the crate, it takes words in any script. A
+/// word is trimmed and lowercased before the program runs on it.
+#[derive(Debug, Clone, Copy)]
+pub struct Snowball {
+    name: &'static str,
+    external: fn(&mut Env) -> bool,
+}
+
+impl Snowball {
+    /// The stemmer for `language`, one of `LANGUAGES`, or `None` for any
+    /// other.
+    pub fn new(language: &str) -> Option<Snowball> {
+        generated::external(language).map(|(name, external)| Snowball { name, external })
+    }
+
+    /// A stemmer that runs `external`, a routine generated by `compile`.
+    pub fn with_external(name: &'static str, external: fn(&mut Env) -> bool) -> Snowball {
+        Snowball { name, external }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl Stem for Snowball {
+    fn stem<'a>(&self, word: &'a str) -> Result<Cow<'a, str>, StemError> {
+        validate_unicode(word)?;
+        let word = word.trim();
+        let mut env = Env::new(word.to_lowercase());
+        (self.external)(&mut env);
+        Ok(borrow_prefix(word, &env.into_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm, porter2, StemErrorKind};
+
+    #[test]
+    fn test_snowball() -> Result<(), StemError> {
+        assert_eq!(LANGUAGES.len(), 11);
+        assert!(Snowball::new("klingon").is_none());
+        let english = Snowball::new("english").unwrap();
+        assert_eq!(english.name(), "english");
+        for word in &["generously", "Caresses", "dying", "consigned", "skies"] {
+            assert_eq!(english.stem(word)?, porter2::stem(word)?);
+        }
+        assert!(matches!(english.stem(" cats"), Ok(Cow::Borrowed("cat"))));
+        assert!(matches!(english.stem("Cats"), Ok(Cow::Owned(_))));
+        assert_eq!(
+            english.stem(" ").unwrap_err().kind(),
+            StemErrorKind::WhitespaceOnly
+        );
+
+        let german = algorithm("snowball-german").unwrap();
+        assert_eq!(german.stem("Häuser")?, "haus");
+        assert_eq!(german.stem("aufeinanderfolgenden")?, "aufeinanderfolg");
+        assert_eq!(
+            Snowball::new("russian").unwrap().stem("вечерний")?,
+            "вечерн"
+        );
+        assert!(algorithm("snowball-klingon").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_external() -> Result<(), StemError> {
+        // Drops a final s, as a program compiled by a user of the crate might.
+        fn stem(env: &mut Env) -> bool {
+            env.cursor = env.limit;
+            env.ket = env.cursor;
+            if !env.eq_s_b("s") {
+                return false;
+            }
+            env.bra = env.cursor;
+            env.slice_del()
+        }
+        let stemmer = Snowball::with_external("plural", stem);
+        assert_eq!(stemmer.name(), "plural");
+        assert!(matches!(stemmer.stem("cats"), Ok(Cow::Borrowed("cat"))));
+        assert_eq!(stemmer.stem("dog")?, "dog");
+        Ok(())
+    }
+}
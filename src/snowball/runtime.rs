@@ -0,0 +1,453 @@
+// What the code generated from Snowball sources runs on: the string being
+// stemmed with its cursor, limits and slice, and the search in an `among`
+// table. The operations follow those of the C runtime that ships with
+// Snowball, working on the bytes of UTF-8 text and moving by whole
+// characters, so generated stemmers behave the same as the C ones.
+
+/// One string of an `among` table.
+///
+/// The strings of a table are sorted, forwards for a forward `among` and
+/// from the end for a backward one, and each refers to the longest other
+/// string of the table it starts (or ends) with, so a failed match can fall
+/// back on the shorter strings.
+pub struct Among<C> {
+    pub string: &'static str,
+    /// The index of the longest string of the table that this one starts
+    /// (or ends) with.
+    pub substring: Option<usize>,
+    /// Which of the `among`'s commands the string selects, counting from 1.
+    pub result: i32,
+    /// A routine that must also succeed for the string to match.
+    pub condition: Option<fn(&mut Env, &mut C) -> bool>,
+}
+
+/// The state of a generated stemmer: the word, its cursor and limits, and the
+/// slice marked with `[` and `]`. All positions are byte offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Env {
+    pub current: String,
+    pub cursor: usize,
+    pub limit: usize,
+    pub limit_backward: usize,
+    pub bra: usize,
+    pub ket: usize,
+}
+
+impl Env {
+    /// An environment for `word`, with the cursor at its start and the slice
+    /// covering all of it.
+    pub fn new(word: String) -> Env {
+        let len = word.len();
+        Env {
+            current: word,
+            cursor: 0,
+            limit: len,
+            limit_backward: 0,
+            bra: 0,
+            ket: len,
+        }
+    }
+
+    /// The word as the stemmer left it.
+    pub fn into_string(self) -> String {
+        self.current
+    }
+
+    /// The length of the word in bytes.
+    pub fn size(&self) -> i32 {
+        self.current.len() as i32
+    }
+
+    /// The length of the word in characters.
+    pub fn length(&self) -> i32 {
+        self.current.chars().count() as i32
+    }
+
+    /// Moves the cursor over the next character, unless it is at the limit.
+    pub fn next_char(&mut self) -> bool {
+        match self.char_after() {
+            Some(c) => {
+                self.cursor += c.len_utf8();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the cursor back over the previous character, unless it is at the
+    /// backward limit.
+    pub fn previous_char(&mut self) -> bool {
+        match self.char_before() {
+            Some(c) => {
+                self.cursor -= c.len_utf8();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the cursor over `n` characters, unless that passes the limit.
+    pub fn hop(&mut self, n: i32) -> bool {
+        let cursor = self.cursor;
+        for _ in 0..n.max(0) {
+            if !self.next_char() {
+                self.cursor = cursor;
+                return false;
+            }
+        }
+        n >= 0
+    }
+
+    /// Moves the cursor back over `n` characters, unless that passes the
+    /// backward limit.
+    pub fn hop_back(&mut self, n: i32) -> bool {
+        let cursor = self.cursor;
+        for _ in 0..n.max(0) {
+            if !self.previous_char() {
+                self.cursor = cursor;
+                return false;
+            }
+        }
+        n >= 0
+    }
+
+    /// Moves the cursor to `mark`, unless that is behind it.
+    pub fn to_mark(&mut self, mark: i32) -> bool {
+        if mark < self.cursor as i32 || mark > self.size() {
+            return false;
+        }
+        self.cursor = mark as usize;
+        true
+    }
+
+    /// Moves the cursor back to `mark`, unless that is ahead of it.
+    pub fn to_mark_back(&mut self, mark: i32) -> bool {
+        if mark > self.cursor as i32 || mark < 0 {
+            return false;
+        }
+        self.cursor = mark as usize;
+        true
+    }
+
+    /// Moves the cursor over `s` if the word goes on with it.
+    pub fn eq_s(&mut self, s: &str) -> bool {
+        let end = self.cursor + s.len();
+        if end > self.limit || &self.current.as_bytes()[self.cursor..end] != s.as_bytes() {
+            return false;
+        }
+        self.cursor = end;
+        true
+    }
+
+    /// Moves the cursor back over `s` if the word comes to the cursor with it.
+    pub fn eq_s_b(&mut self, s: &str) -> bool {
+        if self.cursor < self.limit_backward + s.len()
+            || &self.current.as_bytes()[self.cursor - s.len()..self.cursor] != s.as_bytes()
+        {
+            return false;
+        }
+        self.cursor -= s.len();
+        true
+    }
+
+    /// Moves the cursor over the next character if it is in the grouping.
+    pub fn in_grouping(&mut self, grouping: fn(char) -> bool) -> bool {
+        self.char_after().is_some_and(grouping) && self.next_char()
+    }
+
+    /// Moves the cursor back over the previous character if it is in the
+    /// grouping.
+    pub fn in_grouping_b(&mut self, grouping: fn(char) -> bool) -> bool {
+        self.char_before().is_some_and(grouping) && self.previous_char()
+    }
+
+    /// Moves the cursor over the next character if it is not in the grouping.
+    pub fn out_grouping(&mut self, grouping: fn(char) -> bool) -> bool {
+        self.char_after().is_some_and(|c| !grouping(c)) && self.next_char()
+    }
+
+    /// Moves the cursor back over the previous character if it is not in the
+    /// grouping.
+    pub fn out_grouping_b(&mut self, grouping: fn(char) -> bool) -> bool {
+        self.char_before().is_some_and(|c| !grouping(c)) && self.previous_char()
+    }
+
+    /// Replaces the slice with `s`.
+    pub fn slice_from(&mut self, s: &str) -> bool {
+        if !self.slice_is_valid() {
+            return false;
+        }
+        self.replace(self.bra, self.ket, s);
+        true
+    }
+
+    /// Deletes the slice.
+    pub fn slice_del(&mut self) -> bool {
+        self.slice_from("")
+    }
+
+    /// Copies the slice into `s`.
+    pub fn slice_to(&self, s: &mut String) -> bool {
+        if !self.slice_is_valid() {
+            return false;
+        }
+        s.clear();
+        s.push_str(&self.current[self.bra..self.ket]);
+        true
+    }
+
+    /// Inserts `s` at the cursor and moves the cursor past it.
+    pub fn insert(&mut self, s: &str) {
+        let (cursor, adjustment) = (self.cursor, s.len() as isize);
+        self.replace(cursor, cursor, s);
+        if cursor <= self.bra {
+            self.bra = (self.bra as isize + adjustment) as usize;
+        }
+        if cursor <= self.ket {
+            self.ket = (self.ket as isize + adjustment) as usize;
+        }
+    }
+
+    /// Inserts `s` at the cursor, leaving the cursor in front of it.
+    pub fn attach(&mut self, s: &str) {
+        let cursor = self.cursor;
+        self.insert(s);
+        self.cursor = cursor;
+    }
+
+    /// Finds the longest string of the table that the word goes on with from
+    /// the cursor, and whose condition holds, and moves the cursor over it.
+    /// Returns its result, or 0 when no string matches.
+    pub fn find_among<C>(&mut self, amongs: &[Among<C>], context: &mut C) -> i32 {
+        let (c, l) = (self.cursor, self.limit);
+        let (mut i, mut j) = (0, amongs.len());
+        let (mut common_i, mut common_j) = (0, 0);
+        let mut first_key_inspected = false;
+        loop {
+            let k = i + ((j - i) >> 1);
+            let mut diff = 0;
+            let mut common = common_i.min(common_j);
+            let string = amongs[k].string.as_bytes();
+            for &b in &string[common.min(string.len())..] {
+                if c + common == l {
+                    diff = -1;
+                    break;
+                }
+                diff = i32::from(self.current.as_bytes()[c + common]) - i32::from(b);
+                if diff != 0 {
+                    break;
+                }
+                common += 1;
+            }
+            if diff < 0 {
+                j = k;
+                common_j = common;
+            } else {
+                i = k;
+                common_i = common;
+            }
+            if j - i <= 1 {
+                if i > 0 || j == i || first_key_inspected {
+                    break;
+                }
+                first_key_inspected = true;
+            }
+        }
+        loop {
+            let among = &amongs[i];
+            if common_i >= among.string.len() {
+                self.cursor = c + among.string.len();
+                match among.condition {
+                    None => return among.result,
+                    Some(condition) => {
+                        let result = condition(self, context);
+                        self.cursor = c + among.string.len();
+                        if result {
+                            return among.result;
+                        }
+                    }
+                }
+            }
+            match among.substring {
+                Some(substring) => i = substring,
+                None => return 0,
+            }
+        }
+    }
+
+    /// Finds the longest string of the table that the word comes to the
+    /// cursor with, and whose condition holds, and moves the cursor back over
+    /// it. Returns its result, or 0 when no string matches.
+    pub fn find_among_b<C>(&mut self, amongs: &[Among<C>], context: &mut C) -> i32 {
+        let (c, lb) = (self.cursor, self.limit_backward);
+        let (mut i, mut j) = (0, amongs.len());
+        let (mut common_i, mut common_j) = (0, 0);
+        let mut first_key_inspected = false;
+        loop {
+            let k = i + ((j - i) >> 1);
+            let mut diff = 0;
+            let mut common = common_i.min(common_j);
+            let string = amongs[k].string.as_bytes();
+            for &b in string[..string.len().saturating_sub(common)].iter().rev() {
+                if c - common == lb {
+                    diff = -1;
+                    break;
+                }
+                diff = i32::from(self.current.as_bytes()[c - common - 1]) - i32::from(b);
+                if diff != 0 {
+                    break;
+                }
+                common += 1;
+            }
+            if diff < 0 {
+                j = k;
+                common_j = common;
+            } else {
+                i = k;
+                common_i = common;
+            }
+            if j - i <= 1 {
+                if i > 0 || j == i || first_key_inspected {
+                    break;
+                }
+                first_key_inspected = true;
+            }
+        }
+        loop {
+            let among = &amongs[i];
+            if common_i >= among.string.len() {
+                self.cursor = c - among.string.len();
+                match among.condition {
+                    None => return among.result,
+                    Some(condition) => {
+                        let result = condition(self, context);
+                        self.cursor = c - among.string.len();
+                        if result {
+                            return among.result;
+                        }
+                    }
+                }
+            }
+            match among.substring {
+                Some(substring) => i = substring,
+                None => return 0,
+            }
+        }
+    }
+
+    fn char_after(&self) -> Option<char> {
+        if self.cursor >= self.limit {
+            return None;
+        }
+        self.current[self.cursor..].chars().next()
+    }
+
+    fn char_before(&self) -> Option<char> {
+        if self.cursor <= self.limit_backward {
+            return None;
+        }
+        self.current[..self.cursor].chars().next_back()
+    }
+
+    fn slice_is_valid(&self) -> bool {
+        self.bra <= self.ket
+            && self.ket <= self.limit
+            && self.limit <= self.current.len()
+            && self.current.is_char_boundary(self.bra)
+            && self.current.is_char_boundary(self.ket)
+    }
+
+    // Replaces the text from `bra` to `ket` with `s`, moving the limit and
+    // the cursor to match.
+    fn replace(&mut self, bra: usize, ket: usize, s: &str) {
+        let adjustment = s.len() as isize - (ket - bra) as isize;
+        self.current.replace_range(bra..ket, s);
+        self.limit = (self.limit as isize + adjustment) as usize;
+        if self.cursor >= ket {
+            self.cursor = (self.cursor as isize + adjustment) as usize;
+        } else if self.cursor > bra {
+            self.cursor = bra;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn among(string: &'static str, substring: Option<usize>, result: i32) -> Among<()> {
+        Among {
+            string,
+            substring,
+            result,
+            condition: None,
+        }
+    }
+
+    #[test]
+    fn test_find_among() {
+        let amongs = [
+            among("a", None, 1),
+            among("ab", Some(0), 2),
+            among("b", None, 3),
+        ];
+        let mut env = Env::new("abc".to_string());
+        assert_eq!(env.find_among(&amongs, &mut ()), 2);
+        assert_eq!(env.cursor, 2);
+        env.cursor = 0;
+        env.limit = 1;
+        assert_eq!(env.find_among(&amongs, &mut ()), 1);
+        assert_eq!(env.find_among(&amongs, &mut ()), 0);
+
+        // Backwards the strings are sorted from the end.
+        let amongs = [
+            among("a", None, 1),
+            among("ca", Some(0), 2),
+            among("b", None, 3),
+        ];
+        let mut env = Env::new("bca".to_string());
+        env.cursor = 3;
+        assert_eq!(env.find_among_b(&amongs, &mut ()), 2);
+        assert_eq!(env.cursor, 1);
+        assert_eq!(env.find_among_b(&amongs, &mut ()), 3);
+        assert_eq!(env.find_among_b(&amongs, &mut ()), 0);
+    }
+
+    #[test]
+    fn test_characters() {
+        let mut env = Env::new("\u{e4}b".to_string());
+        assert!(env.in_grouping(|c| c == '\u{e4}'));
+        assert_eq!(env.cursor, 2);
+        assert!(!env.hop(2));
+        assert_eq!(env.cursor, 2);
+        assert!(env.previous_char());
+        assert_eq!((env.size(), env.length()), (3, 2));
+        assert!(env.eq_s("\u{e4}"));
+        assert!(!env.eq_s_b("a"));
+    }
+
+    #[test]
+    fn test_slices() {
+        let mut env = Env::new("running".to_string());
+        env.cursor = 7;
+        env.bra = 3;
+        assert!(env.slice_from("ed"));
+        assert_eq!(
+            (env.current.as_str(), env.cursor, env.limit),
+            ("runed", 5, 5)
+        );
+        env.ket = 5;
+        env.cursor = 3;
+        env.insert("n");
+        assert_eq!(
+            (env.current.as_str(), env.cursor, env.bra, env.ket),
+            ("runned", 4, 4, 6)
+        );
+        let mut slice = String::new();
+        assert!(env.slice_to(&mut slice));
+        assert_eq!(slice, "ed");
+        env.bra = 5;
+        env.ket = 4;
+        assert!(!env.slice_del());
+    }
+}
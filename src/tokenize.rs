@@ -0,0 +1,260 @@
+// Splitting running text into the words `stem` expects.
+//
+// A token is a run of letters and digits. Inside a token, an apostrophe
+// between two letters is kept (o'clock), as is a '.' or ',' between two digits
+// (3.14, 1,000). Everything else, hyphens included, separates tokens. The
+// clitics of English contractions are split off as tokens of their own, the
+// way the Penn Treebank does: don't gives do and n't, dog's gives dog and 's.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::{stem_str, StemError};
+
+// The endings split off after an apostrophe, besides n't.
+const CLITICS: &[&str] = &["s", "re", "ve", "ll", "d", "m"];
+
+/// A word in a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// The word as it appears in the text.
+    pub text: &'a str,
+    /// The byte range of the word in the text.
+    pub span: Range<usize>,
+}
+
+/// A token's stem together with the part of the text it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StemSpan<'a> {
+    /// The stem of the token.
+    pub stem: Cow<'a, str>,
+    /// The byte range of the token in the text.
+    pub span: Range<usize>,
+    /// The length in bytes of the prefix that the token and the stem share,
+    /// ignoring case: 7 for `Connections` and `connect`.
+    pub prefix_len: usize,
+}
+
+impl<'a> StemSpan<'a> {
+    /// The byte range in the text of the prefix shared with the stem, the part
+    /// to highlight for a match on it.
+    pub fn prefix(&self) -> Range<usize> {
+        self.span.start..self.span.start + self.prefix_len
+    }
+}
+
+/// An iterator over the tokens of a text, returned by `tokenize`.
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    text: &'a str,
+    position: usize,
+    // The clitic split off the last token, returned next.
+    pending: Option<Token<'a>>,
+}
+
+/// Splits `text` into word tokens.
+pub fn tokenize(text: &str) -> Tokens<'_> {
+    Tokens {
+        text,
+        position: 0,
+        pending: None,
+    }
+}
+
+/// Splits `text` into tokens and stems each one with `stem_str`. A token that
+/// cannot be stemmed, such as one with a non-ASCII letter, comes with the
+/// error instead.
+pub fn stem_text(text: &str) -> impl Iterator<Item = (Token<'_>, Result<Cow<'_, str>, StemError>)> {
+    tokenize(text).map(|token| {
+        let stem = stem_str(token.text);
+        (token, stem)
+    })
+}
+
+/// Splits `text` into tokens like `stem_text`, giving each stem with the range
+/// of its token in the text and the length of the prefix the two share.
+pub fn stem_spans(
+    text: &str,
+) -> impl Iterator<Item = (Token<'_>, Result<StemSpan<'_>, StemError>)> {
+    stem_text(text).map(|(token, stem)| {
+        let span = stem.map(|stem| StemSpan {
+            prefix_len: shared_prefix_len(token.text, &stem),
+            span: token.span.clone(),
+            stem,
+        });
+        (token, span)
+    })
+}
+
+// Stems are lowercase, so letters are compared without regard to case.
+fn shared_prefix_len(word: &str, stem: &str) -> usize {
+    word.bytes()
+        .zip(stem.bytes())
+        .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+        .count()
+}
+
+impl<'a> Tokens<'a> {
+    fn token(&self, span: Range<usize>) -> Token<'a> {
+        Token {
+            text: &self.text[span.clone()],
+            span,
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if let Some(token) = self.pending.take() {
+            return Some(token);
+        }
+        let rest = &self.text[self.position..];
+        let start = match rest.char_indices().find(|(_, c)| c.is_alphanumeric()) {
+            Some((offset, _)) => self.position + offset,
+            None => {
+                self.position = self.text.len();
+                return None;
+            }
+        };
+        let end = word_end(self.text, start);
+        self.position = end;
+        match clitic_start(&self.text[start..end]) {
+            Some(split) => {
+                self.pending = Some(self.token(start + split..end));
+                Some(self.token(start..start + split))
+            }
+            None => Some(self.token(start..end)),
+        }
+    }
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+// The end of the token that starts at `start`.
+fn word_end(text: &str, start: usize) -> usize {
+    let mut chars = text[start..].char_indices().peekable();
+    let mut end = start;
+    let mut previous: Option<char> = None;
+    while let Some((offset, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let between = |test: fn(&char) -> bool| {
+            previous.is_some_and(|p| test(&p)) && next.is_some_and(|n| test(&n))
+        };
+        let joins = c.is_alphanumeric()
+            || (is_apostrophe(c) && between(|c| c.is_alphabetic()))
+            || ((c == '.' || c == ',') && between(char::is_ascii_digit));
+        if !joins {
+            break;
+        }
+        end = start + offset + c.len_utf8();
+        previous = Some(c);
+    }
+    end
+}
+
+// Where the clitic at the end of `word` starts, if it has one.
+fn clitic_start(word: &str) -> Option<usize> {
+    let (index, apostrophe) = word.char_indices().rev().find(|&(_, c)| is_apostrophe(c))?;
+    let after = &word[index + apostrophe.len_utf8()..];
+    if CLITICS
+        .iter()
+        .any(|clitic| clitic.eq_ignore_ascii_case(after))
+    {
+        return Some(index);
+    }
+    let before = &word[..index];
+    if after.eq_ignore_ascii_case("t") && before.len() > 1 && before.ends_with(['n', 'N']) {
+        return Some(index - 1);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<&str> {
+        tokenize(text).map(|token| token.text).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            words("The cats' toys -- aren't they?"),
+            ["The", "cats", "toys", "are", "n't", "they"]
+        );
+        assert_eq!(
+            words("state-of-the-art (o'clock)"),
+            ["state", "of", "the", "art", "o'clock"]
+        );
+        assert_eq!(
+            words("In 1990, 3.14 and 1,000 were 2nd."),
+            ["In", "1990", "3.14", "and", "1,000", "were", "2nd"]
+        );
+        assert_eq!(
+            words("John\u{2019}s we'LL I'd can't 'quoted'"),
+            [
+                "John",
+                "\u{2019}s",
+                "we",
+                "'LL",
+                "I",
+                "'d",
+                "ca",
+                "n't",
+                "quoted"
+            ]
+        );
+        assert_eq!(words("caf\u{e9} au lait"), ["caf\u{e9}", "au", "lait"]);
+        assert!(words(" -- ").is_empty());
+        assert!(words("").is_empty());
+    }
+
+    #[test]
+    fn test_spans() {
+        let text = "Don\u{2019}t stop, 3.5-times!";
+        let tokens: Vec<Token> = tokenize(text).collect();
+        assert_eq!(tokens.len(), 5);
+        for token in &tokens {
+            assert_eq!(&text[token.span.clone()], token.text);
+        }
+        assert_eq!(tokens[0].span, 0..2);
+        assert_eq!(tokens[1].span, 2..7);
+        assert_eq!(tokens[4].span, 18..23);
+    }
+
+    #[test]
+    fn test_stem_text() {
+        let stems: Vec<_> = stem_text("Connections, connecting caf\u{e9}s")
+            .map(|(token, stem)| (token.text, stem.map(Cow::into_owned)))
+            .collect();
+        assert_eq!(stems[0], ("Connections", Ok("connect".to_string())));
+        assert_eq!(stems[1], ("connecting", Ok("connect".to_string())));
+        assert_eq!(stems[2].0, "caf\u{e9}s");
+        assert!(stems[2].1.is_err());
+    }
+
+    #[test]
+    fn test_stem_spans() {
+        let text = "Connections, HAPPY ponies; caf\u{e9}s";
+        let spans: Vec<_> = stem_spans(text).collect();
+        let prefixes: Vec<_> = spans
+            .iter()
+            .filter_map(|(_, span)| span.as_ref().ok())
+            .map(|span| (span.stem.as_ref(), &text[span.prefix()]))
+            .collect();
+        assert_eq!(
+            prefixes,
+            [("connect", "Connect"), ("happi", "HAPP"), ("poni", "poni")]
+        );
+        let (token, span) = &spans[0];
+        let span = span.as_ref().unwrap();
+        assert_eq!(span.span, token.span);
+        assert_eq!((span.span.clone(), span.prefix_len), (0..11, 7));
+        assert!(spans[3].1.is_err());
+    }
+}
@@ -0,0 +1,80 @@
+// Step-by-step records of the Porter pipeline, as returned by `stem_traced`.
+
+use std::fmt;
+
+/// What one step of the Porter pipeline did to the word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// The step, named after its function: `"stem1a"` through `"stem5b"`.
+    pub step: &'static str,
+    /// The suffix matched by the rule that fired, or `None` if none did.
+    pub suffix: Option<String>,
+    /// The text the suffix was replaced with.
+    pub replacement: Option<String>,
+    /// The measure m of the stem in front of the suffix, for the rules whose
+    /// condition depends on it.
+    pub measure: Option<usize>,
+    pub before: String,
+    pub after: String,
+}
+
+/// The steps taken to stem a word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    /// The word as given to `stem_traced`.
+    pub word: String,
+    /// The stem, the same as `stem` returns for the word.
+    pub stem: String,
+    /// Every step that ran, in order. Words of two letters or fewer are not
+    /// stemmed and have no steps.
+    pub steps: Vec<TraceStep>,
+}
+
+impl Trace {
+    /// The steps in which a rule fired.
+    pub fn changes(&self) -> impl Iterator<Item = &TraceStep> {
+        self.steps.iter().filter(|step| step.suffix.is_some())
+    }
+}
+
+const HEADERS: [&str; 6] = ["step", "suffix", "replacement", "m", "before", "after"];
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows: Vec<[String; 6]> = self
+            .steps
+            .iter()
+            .map(|step| {
+                let or_dash = |s: &Option<String>| match s {
+                    Some(s) if s.is_empty() => "\"\"".to_string(),
+                    Some(s) => s.clone(),
+                    None => "-".to_string(),
+                };
+                [
+                    step.step.to_string(),
+                    or_dash(&step.suffix),
+                    or_dash(&step.replacement),
+                    step.measure.map_or("-".to_string(), |m| m.to_string()),
+                    step.before.clone(),
+                    step.after.clone(),
+                ]
+            })
+            .collect();
+        let mut widths = HEADERS.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        writeln!(f, "{} -> {}", self.word, self.stem)?;
+        let headers = HEADERS.map(str::to_string);
+        for row in std::iter::once(&headers).chain(&rows) {
+            let mut line = String::new();
+            for (cell, width) in row.iter().zip(&widths) {
+                line.push_str(&format!("{:<width$}  ", cell, width = width));
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
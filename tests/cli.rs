@@ -0,0 +1,128 @@
+// Runs the `stem` binary over small inputs and checks what it writes.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_stem"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run stem");
+    // The input is written from another thread so that stem can write more
+    // output than the pipe holds, and may stop at a bad word and exit before
+    // it has read all its input.
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_string();
+    let writer = std::thread::spawn(move || match stdin.write_all(input.as_bytes()) {
+        Err(error) if error.kind() != std::io::ErrorKind::BrokenPipe => panic!("{}", error),
+        _ => {}
+    });
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    output
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_formats() {
+    let input = "Caresses  ponies\n\thopping\n";
+    let output = run(&[], input);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "caress\nponi\nhop\n");
+
+    let output = run(&["--format", "pairs"], input);
+    assert_eq!(
+        stdout(&output),
+        "Caresses\tcaress\nponies\tponi\nhopping\thop\n"
+    );
+
+    let output = run(&["-f", "text"], input);
+    assert_eq!(stdout(&output), "caress  poni\n\thop\n");
+}
+
+#[test]
+fn test_punctuation() {
+    let input = "Connected, connecting.\n(ponies) \"hopping\"--cats\n";
+    let output = run(&[], input);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "connect\nconnect\nponi\nhop\ncat\n");
+
+    let output = run(&["-f", "text"], input);
+    assert_eq!(stdout(&output), "connect, connect.\n(poni) \"hop\"--cat\n");
+
+    // The input is read in chunks of 64 KiB, and a word at the end of one goes
+    // on into the next.
+    let input = "connected, ".repeat(6000);
+    let output = run(&["-f", "text"], &input);
+    assert_eq!(stdout(&output), "connect, ".repeat(6000));
+}
+
+#[test]
+fn test_errors() {
+    let input = "cats caf\u{e9}s dogs\n";
+    let output = run(&[], input);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "cat\n");
+    assert_eq!(
+        std::str::from_utf8(&output.stderr).unwrap(),
+        "stem: <stdin>:1: \"caf\u{e9}s\": Invalid Input: non-ASCII character '\u{e9}' at byte 3\n"
+    );
+
+    let output = run(&["--errors=skip"], input);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "cat\ndog\n");
+
+    let output = run(&["-e", "pass", "-f", "text"], input);
+    assert_eq!(stdout(&output), "cat caf\u{e9}s dog\n");
+
+    let output = run(&["--format", "words"], input);
+    assert_eq!(output.status.code(), Some(2));
+
+    // A token too long to keep back is split, but not inside a character.
+    let word = format!("a{}", "\u{e9}".repeat(100_000));
+    let output = run(&["-e", "pass"], &word);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout(&output).lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines.concat(), word);
+}
+
+#[test]
+fn test_files() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/porter");
+    let voc = fixtures.join("voc.txt");
+    let output = run(&[voc.to_str().unwrap()], "");
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        std::fs::read_to_string(fixtures.join("output.txt")).unwrap()
+    );
+
+    let output = run(&["-", "no-such-file"], "cats\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stdout(&output), "cat\n");
+}
+
+#[test]
+fn test_classes() {
+    let input = "Connected, connecting caf\u{e9}s\nponies\n";
+    let output = run(&["--classes", "tsv"], input);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "stem\tsize\twords\nconnect\t2\tconnected connecting\nponi\t1\tponies\n"
+    );
+
+    let output = run(&["-c", "json"], input);
+    assert!(stdout(&output).starts_with("[\n{\"stem\": \"connect\", \"size\": 2,"));
+
+    let output = run(&["-c", "xml"], input);
+    assert_eq!(output.status.code(), Some(2));
+}
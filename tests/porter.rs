@@ -0,0 +1,58 @@
+// Checks `stem`, and a `Stemmer` with the default `PorterConfig`, against
+// Martin Porter's vocabulary and expected output in `tests/fixtures/porter`,
+// one word per line in each file.
+
+use std::fs;
+use std::path::Path;
+
+use stemmer::{stem, PorterConfig, Stemmer};
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/porter")
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+#[test]
+fn test_porter_vocabulary() {
+    let voc = fixture("voc.txt");
+    let output = fixture("output.txt");
+    let words: Vec<&str> = voc.lines().collect();
+    let expected: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        words.len(),
+        expected.len(),
+        "fixture files differ in length"
+    );
+
+    let mismatches: Vec<String> = words
+        .iter()
+        .zip(&expected)
+        .filter_map(|(word, expected)| match stem(word.to_string()) {
+            Ok(ref stem) if stem == expected => None,
+            Ok(stem) => Some(format!("{}: expected {}, got {}", word, expected, stem)),
+            Err(e) => Some(format!("{}: expected {}, got error {}", word, expected, e)),
+        })
+        .collect();
+    assert!(
+        mismatches.is_empty(),
+        "{} of {} words mismatched:\n{}",
+        mismatches.len(),
+        words.len(),
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn test_default_config() {
+    let mut stemmer = Stemmer::builder().config(PorterConfig::default()).build();
+    let mut stem = String::new();
+    for (word, expected) in fixture("voc.txt")
+        .lines()
+        .zip(fixture("output.txt").lines())
+    {
+        stemmer.stem_into(word, &mut stem).unwrap();
+        assert_eq!(stem, expected, "{}", word);
+    }
+}
@@ -0,0 +1,44 @@
+// Checks `porter2::stem` against the Snowball English vocabulary and expected
+// output in `tests/fixtures/porter2`, one word per line in each file.
+
+use std::fs;
+use std::path::Path;
+
+use stemmer::porter2;
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/porter2")
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+#[test]
+fn test_porter2_vocabulary() {
+    let voc = fixture("voc.txt");
+    let output = fixture("output.txt");
+    let words: Vec<&str> = voc.lines().collect();
+    let expected: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        words.len(),
+        expected.len(),
+        "fixture files differ in length"
+    );
+
+    let mismatches: Vec<String> = words
+        .iter()
+        .zip(&expected)
+        .filter_map(|(word, expected)| match porter2::stem(word) {
+            Ok(ref stem) if stem == expected => None,
+            Ok(stem) => Some(format!("{}: expected {}, got {}", word, expected, stem)),
+            Err(e) => Some(format!("{}: expected {}, got error {}", word, expected, e)),
+        })
+        .collect();
+    assert!(
+        mismatches.is_empty(),
+        "{} of {} words mismatched:\n{}",
+        mismatches.len(),
+        words.len(),
+        mismatches.join("\n")
+    );
+}
@@ -0,0 +1,79 @@
+// Checks each of the Snowball stemmers against its vocabulary and expected
+// output in `tests/fixtures/snowball/LANGUAGE`, or for English in
+// `tests/fixtures/porter2`, one word per line in each file.
+
+use std::fs;
+use std::path::Path;
+
+use stemmer::snowball::{Snowball, LANGUAGES};
+use stemmer::Stem;
+
+fn fixture(language: &str, name: &str) -> String {
+    let directory = match language {
+        "english" => "porter2".to_string(),
+        _ => format!("snowball/{}", language),
+    };
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(directory)
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+// The words of the vocabulary of `language` that its stemmer gets wrong.
+fn mismatches(language: &str) -> Vec<String> {
+    let stemmer = Snowball::new(language).unwrap();
+    let voc = fixture(language, "voc.txt");
+    let output = fixture(language, "output.txt");
+    let words: Vec<&str> = voc.lines().collect();
+    let expected: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        words.len(),
+        expected.len(),
+        "{}: fixture files differ in length",
+        language
+    );
+
+    words
+        .iter()
+        .zip(&expected)
+        // The reference ran the program on a word as it is, but `stem`
+        // lowercases it first, so the few words with capitals are left out.
+        .filter(|(word, _)| word.to_lowercase() == **word)
+        .filter_map(|(word, expected)| match stemmer.stem(word) {
+            Ok(ref stem) if stem == expected => None,
+            // `stem` rejects the blank lines of the Spanish list.
+            Err(_) if word.is_empty() && expected.is_empty() => None,
+            Ok(stem) => Some(format!(
+                "{}: {}: expected {}, got {}",
+                language, word, expected, stem
+            )),
+            Err(e) => Some(format!(
+                "{}: {}: expected {}, got error {}",
+                language, word, expected, e
+            )),
+        })
+        .collect()
+}
+
+#[test]
+fn test_snowball_vocabularies() {
+    let fixtures =
+        fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/snowball"))
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().is_dir())
+            .count();
+    // Every language but English, which shares the Porter2 fixture.
+    assert_eq!(fixtures, LANGUAGES.len() - 1);
+
+    let mismatches: Vec<String> = LANGUAGES
+        .iter()
+        .flat_map(|language| mismatches(language))
+        .collect();
+    assert!(
+        mismatches.is_empty(),
+        "{} words mismatched:\n{}",
+        mismatches.len(),
+        mismatches.join("\n")
+    );
+}